@@ -0,0 +1,23 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use nats_jwt_rs::{account::Account, chain::verify_many};
+use nkeys::KeyPair;
+
+fn bench_verify_many(c: &mut Criterion) {
+    let operator_key = KeyPair::new_operator();
+    let tokens: Vec<String> = (0..1000)
+        .map(|i| {
+            Account::new_claims(format!("account-{i}"), KeyPair::new_account().public_key())
+                .encode(&operator_key)
+                .unwrap()
+        })
+        .collect();
+    let tokens: Vec<&str> = tokens.iter().map(String::as_str).collect();
+    let trusted = KeyPair::from_public_key(&operator_key.public_key()).unwrap();
+
+    c.bench_function("verify_many 1000 accounts", |b| {
+        b.iter(|| verify_many(&tokens, &trusted))
+    });
+}
+
+criterion_group!(benches, bench_verify_many);
+criterion_main!(benches);
@@ -1,16 +1,24 @@
 use crate::{
+    summary_line,
     types::{
-        Export, GenericFields, Import, Info, Limits, NatsLimits, Permission, Permissions,
-        SigningKey, NO_LIMIT,
+        is_valid_subject, subject_has_wildcards, subject_is_subset, Export, ExportType,
+        GenericFields, Import, Info, NatsLimits, Permission, Permissions, RevocationList,
+        SigningKey, UserScope, NO_LIMIT,
     },
+    user::User,
+    validation::{is_valid_public_key, ValidationIssue, ValidationResults},
     Claim, ClaimType, Claims,
 };
+use anyhow::Result;
 use derive_builder::Builder;
 use indexmap::IndexSet;
+use nkeys::{KeyPair, KeyPairType};
 use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct OperatorLimits {
     #[serde(flatten, skip_serializing_if = "Option::is_none")]
     pub nats: Option<NatsLimits>,
@@ -18,8 +26,9 @@ pub struct OperatorLimits {
     pub account: Option<AccountLimits>,
     #[serde(flatten, skip_serializing_if = "Option::is_none")]
     pub jetstream: Option<JetStreamLimits>,
-    #[serde(flatten, skip_serializing_if = "Option::is_none")]
-    pub tiered_limits: Option<BTreeMap<String, Limits>>,
+    /// JetStream limits per replication tier, such as `R1` or `R3`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tiered_limits: Option<BTreeMap<String, JetStreamLimits>>,
 }
 
 impl Default for OperatorLimits {
@@ -34,6 +43,7 @@ impl Default for OperatorLimits {
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub struct JetStreamLimits {
     #[serde(rename = "mem_storage", skip_serializing_if = "Option::is_none")]
@@ -55,6 +65,7 @@ pub struct JetStreamLimits {
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct AccountLimits {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub imports: Option<i64>,
@@ -83,7 +94,39 @@ impl Default for AccountLimits {
     }
 }
 
+impl AccountLimits {
+    /// Returns the maximum number of client connections, or `None` if unlimited. An absent
+    /// limit is read as zero, as nats.go omits zero values, and other negative values as zero,
+    /// since nats-server blocks all connections for them.
+    pub fn max_connections(&self) -> Option<u64> {
+        Self::count_limit(self.conn)
+    }
+
+    /// Returns the maximum number of leaf node connections, or `None` if unlimited.
+    pub fn max_leafnodes(&self) -> Option<u64> {
+        Self::count_limit(self.leaf)
+    }
+
+    fn count_limit(limit: Option<i64>) -> Option<u64> {
+        match limit.unwrap_or(0) {
+            NO_LIMIT => None,
+            limit => Some(u64::try_from(limit).unwrap_or(0)),
+        }
+    }
+
+    pub fn validate(&self, vr: &mut ValidationResults) {
+        for (kind, limit) in [("connection", self.conn), ("leaf node", self.leaf)] {
+            if let Some(limit) = limit.filter(|l| *l < 0 && *l != NO_LIMIT) {
+                vr.add_error(format!(
+                    "account {kind} limit {limit} must be non-negative or {NO_LIMIT}"
+                ));
+            }
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct WeightedMapping {
     pub subject: String,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -92,9 +135,63 @@ pub struct WeightedMapping {
     pub cluster: Option<String>,
 }
 
+impl WeightedMapping {
+    /// Checks that the transform functions in the destination `subject`, such as
+    /// `{{wildcard(1)}}` or `{{partition(10,1,2)}}`, only reference wildcard positions that
+    /// exist in the mapping's `source` subject.
+    pub fn validate_transform(&self, source: &str) -> Result<()> {
+        let wildcards = source.split('.').filter(|token| *token == "*").count();
+
+        let mut rest = self.subject.as_str();
+        while let Some(start) = rest.find("{{") {
+            let end = rest[start..]
+                .find("}}")
+                .ok_or_else(|| anyhow::anyhow!("unterminated transform in {}", self.subject))?;
+            let function = rest[start + 2..start + end].trim();
+            rest = &rest[start + end + 2..];
+
+            let (name, args) = function
+                .strip_suffix(')')
+                .and_then(|f| f.split_once('('))
+                .ok_or_else(|| anyhow::anyhow!("invalid transform function {function}"))?;
+            let args: Vec<&str> = args.split(',').map(str::trim).collect();
+            let name = name.trim().to_lowercase();
+            let positions = match name.as_str() {
+                "wildcard" => &args[..],
+                // the first argument is the number of partitions, and without wildcard
+                // positions the whole subject is partitioned
+                "partition" => args.get(1..).unwrap_or_default(),
+                "splitfromleft" | "splitfromright" | "slicefromleft" | "slicefromright"
+                | "split" | "left" | "right" => &args[..1],
+                // the only argument is the number of buckets
+                "random" => &[],
+                _ => return Err(anyhow::anyhow!("unknown transform function {function}")),
+            };
+            if positions.is_empty() && !matches!(name.as_str(), "partition" | "random") {
+                return Err(anyhow::anyhow!(
+                    "transform function {function} does not reference a wildcard"
+                ));
+            }
+
+            for position in positions {
+                match position.parse::<usize>() {
+                    Ok(p) if (1..=wildcards).contains(&p) => {}
+                    _ => {
+                        return Err(anyhow::anyhow!(
+                            "transform function {function} references wildcard {position}, but {source} has {wildcards} wildcards"
+                        ))
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
 type Mapping = BTreeMap<String, Vec<WeightedMapping>>;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct ExternalAuthorization {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub auth_users: Option<BTreeSet<String>>,
@@ -104,7 +201,38 @@ pub struct ExternalAuthorization {
     pub xkey: Option<String>,
 }
 
+impl ExternalAuthorization {
+    pub fn validate(&self, vr: &mut ValidationResults) {
+        for user in self.auth_users.iter().flatten() {
+            if !is_valid_public_key(user, KeyPairType::User) {
+                vr.add_error(format!("auth user {user} is not a valid user public key"));
+            }
+        }
+
+        let allowed_accounts = self.allowed_accounts.iter().flatten();
+        for account in allowed_accounts.clone() {
+            if account != "*" && !is_valid_public_key(account, KeyPairType::Account) {
+                vr.add_error(format!(
+                    "allowed account {account} is not a valid account public key"
+                ));
+            }
+        }
+        if allowed_accounts.clone().any(|a| a == "*") && allowed_accounts.count() > 1 {
+            vr.add_error(
+                "allowed accounts cannot combine the * wildcard with specific accounts".to_string(),
+            );
+        }
+
+        if let Some(xkey) = &self.xkey {
+            if !is_valid_public_key(xkey, KeyPairType::Curve) {
+                vr.add_error(format!("xkey {xkey} is not a valid curve public key"));
+            }
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct MsgTrace {
     #[serde(rename = "dest", skip_serializing_if = "Option::is_none")]
     pub destination: Option<String>,
@@ -112,7 +240,32 @@ pub struct MsgTrace {
     pub sampling: Option<u64>,
 }
 
+impl MsgTrace {
+    /// Sets the subject trace events are published to, which must be concrete.
+    pub fn set_destination(&mut self, destination: impl Into<String>) -> Result<()> {
+        let destination = destination.into();
+        check_trace_destination(&destination)?;
+        self.destination = Some(destination);
+        Ok(())
+    }
+}
+
+fn check_trace_destination(destination: &str) -> Result<()> {
+    if !is_valid_subject(destination) {
+        return Err(anyhow::anyhow!(
+            "trace destination {destination} is not a valid subject"
+        ));
+    }
+    if subject_has_wildcards(destination) {
+        return Err(anyhow::anyhow!(
+            "trace destination {destination} contains wildcards"
+        ));
+    }
+    Ok(())
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, Builder)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[builder(setter(into), default)]
 pub struct Account {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -126,7 +279,7 @@ pub struct Account {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub signing_keys: Option<IndexSet<SigningKey>>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub revocations: Option<BTreeMap<String, u64>>,
+    pub revocations: Option<RevocationList>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub default_permissions: Option<Permissions>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -135,7 +288,7 @@ pub struct Account {
     pub authorization: Option<ExternalAuthorization>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub trace: Option<MsgTrace>,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
     pub info: Option<Info>,
     #[serde(flatten)]
     pub generic_fields: GenericFields,
@@ -145,7 +298,7 @@ impl Default for Account {
     fn default() -> Self {
         Self {
             generic_fields: GenericFields {
-                claim_type: ClaimType::Account,
+                claim_type: Self::CLAIM_TYPE,
                 ..Default::default()
             },
             default_permissions: Some(Permissions {
@@ -166,8 +319,184 @@ impl Default for Account {
     }
 }
 
+/// The default maximum length of an encoded account JWT, matching nats-server's default
+/// max payload of 1MB.
+pub const DEFAULT_MAX_ACCOUNT_JWT_LEN: usize = 1024 * 1024;
+
+impl Claims<Account> {
+    /// Warns if the encoded token is likely to exceed `max_len` bytes, for example when the
+    /// server or resolver is configured with a smaller max payload than the default.
+    ///
+    /// [`Claims::validate`] runs this with [`DEFAULT_MAX_ACCOUNT_JWT_LEN`].
+    pub fn validate_encoded_len(&self, max_len: usize, vr: &mut ValidationResults) {
+        match self.estimated_encoded_len() {
+            Ok(len) if len > max_len => vr.add_warning(format!(
+                "account JWT is about {len} bytes, exceeding the maximum of {max_len}"
+            )),
+            Ok(_) => {}
+            Err(e) => vr.add_error(format!("account claims cannot be encoded: {e}")),
+        }
+    }
+}
+
+impl fmt::Display for Claims<Account> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_summary(f)?;
+        let account = &self.nats;
+        summary_line(
+            f,
+            "Signing keys",
+            format!(
+                "{} ({} scoped)",
+                account.signing_keys_iter().count(),
+                account.scoped_signing_keys().count()
+            ),
+        )?;
+        summary_line(f, "Imports", account.imports_iter().count())?;
+        summary_line(f, "Exports", account.exports_iter().count())?;
+        if let Some(limits) = account.account_limits() {
+            match limits.max_connections() {
+                Some(max) => summary_line(f, "Connections", max)?,
+                None => summary_line(f, "Connections", "unlimited")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+fn check_user_permission<'a>(
+    action: &str,
+    user: &Permission,
+    policies: impl Iterator<Item = &'a Permission>,
+    defaults: Option<&Permission>,
+    vr: &mut ValidationResults,
+) {
+    // subjects the user denies as well don't count against the user's grants
+    let policies: Vec<Permission> = policies
+        .map(|policy| Permission {
+            allow: policy.allow.clone(),
+            deny: policy
+                .deny
+                .iter()
+                .filter(|d| !user.deny.iter().any(|u| subject_is_subset(d, u)))
+                .cloned()
+                .collect(),
+        })
+        .collect();
+    for subject in &user.allow {
+        if !policies.iter().any(|policy| policy.allows(subject)) {
+            vr.add_warning(format!(
+                "user allows {action} on {subject}, which the account does not permit"
+            ));
+        }
+    }
+
+    for denied in defaults.iter().flat_map(|d| &d.deny) {
+        let reachable = user.allow.is_empty()
+            || user
+                .allow
+                .iter()
+                .any(|allow| subject_is_subset(allow, denied) || subject_is_subset(denied, allow));
+        let covered = user.deny.iter().any(|deny| subject_is_subset(denied, deny));
+        if reachable && !covered {
+            vr.add_warning(format!(
+                "user does not deny {action} on {denied}, which the account defaults deny"
+            ));
+        }
+    }
+}
+
 impl Claim for Account {
-    fn validate() {}
+    const CLAIM_TYPE: ClaimType = ClaimType::Account;
+
+    fn validate(claims: &Claims<Self>, vr: &mut ValidationResults) {
+        let account = &claims.nats;
+        for import in account.imports_iter() {
+            import.validate(vr);
+        }
+        for export in account.exports_iter() {
+            export.validate(vr);
+        }
+
+        if let Some(authorization) = &account.authorization {
+            authorization.validate(vr);
+        }
+
+        account.validate_trace_consistency(vr);
+        account.validate_default_permissions_cover_exports(vr);
+        account.validate_export_overlap(vr);
+        account.validate_scope_templates(vr);
+        claims.validate_encoded_len(DEFAULT_MAX_ACCOUNT_JWT_LEN, vr);
+
+        if let Some(trace) = &account.trace {
+            if let Some(destination) = &trace.destination {
+                if let Err(e) = check_trace_destination(destination) {
+                    vr.add_error(e.to_string());
+                }
+            }
+            if let Some(sampling) = trace.sampling.filter(|s| *s > 100) {
+                vr.add_error(format!(
+                    "trace sampling {sampling} is not a percentage between 0 and 100"
+                ));
+            }
+        }
+
+        if let Some(limits) = account.account_limits() {
+            limits.validate(vr);
+
+            if limits.wildcard_exports == Some(false) {
+                for export in account
+                    .exports_iter()
+                    .filter(|e| subject_has_wildcards(&e.subject))
+                {
+                    vr.add_error(format!(
+                        "account disallows wildcard exports but exports {}",
+                        export.subject
+                    ));
+                }
+            }
+
+            let counts = [
+                ("imports", account.imports_iter().count(), limits.imports),
+                ("exports", account.exports_iter().count(), limits.exports),
+            ];
+            for (kind, count, limit) in counts {
+                let Some(limit) = limit.filter(|l| *l != NO_LIMIT) else {
+                    continue;
+                };
+                if count as i64 > limit {
+                    vr.add_error(format!(
+                        "account declares {count} {kind}, exceeding its limit of {limit} by {}",
+                        count as i64 - limit
+                    ));
+                }
+            }
+        }
+    }
+
+    fn generic_fields(&self) -> Option<&GenericFields> {
+        Some(&self.generic_fields)
+    }
+
+    fn normalize_permissions(&mut self) {
+        if let Some(permissions) = &mut self.default_permissions {
+            permissions.normalize();
+        }
+        // set members can't be modified in place, so the signing keys are rebuilt in order
+        if let Some(keys) = self.signing_keys.take() {
+            self.signing_keys = Some(
+                keys.into_iter()
+                    .map(|mut key| {
+                        if let Some(template) = key.scope.as_mut().and_then(|s| s.template.as_mut())
+                        {
+                            template.permissions.normalize();
+                        }
+                        key
+                    })
+                    .collect(),
+            );
+        }
+    }
 }
 
 impl Account {
@@ -178,4 +507,1100 @@ impl Account {
         claim.sub = nkey;
         claim
     }
+
+    /// Creates claims for the public key of `key`, which must be an account key.
+    pub fn new_claims_for(name: impl Into<String>, key: &KeyPair) -> Result<Claims<Account>> {
+        if key.key_pair_type() != KeyPairType::Account {
+            return Err(anyhow::anyhow!(
+                "{} is not an account key",
+                key.public_key()
+            ));
+        }
+        Ok(Self::new_claims(name.into(), key.public_key()))
+    }
+
+    pub fn add_import(&mut self, import: Import) {
+        self.imports.get_or_insert_with(Vec::new).push(import);
+    }
+
+    pub fn add_export(&mut self, export: Export) {
+        self.exports.get_or_insert_with(Vec::new).push(export);
+    }
+
+    pub fn imports_iter(&self) -> impl Iterator<Item = &Import> {
+        self.imports.iter().flatten()
+    }
+
+    pub fn exports_iter(&self) -> impl Iterator<Item = &Export> {
+        self.exports.iter().flatten()
+    }
+
+    pub fn signing_keys_iter(&self) -> impl Iterator<Item = &SigningKey> {
+        self.signing_keys.iter().flatten()
+    }
+
+    /// Iterates over the scoped signing keys only, yielding each key with its scope.
+    pub fn scoped_signing_keys(&self) -> impl Iterator<Item = (&str, &UserScope)> {
+        self.signing_keys_iter()
+            .filter_map(|k| k.scope.as_ref().map(|scope| (k.key.as_str(), scope)))
+    }
+
+    pub fn account_limits(&self) -> Option<&AccountLimits> {
+        self.limits.as_ref().and_then(|l| l.account.as_ref())
+    }
+
+    /// Validates a user issued by this account against the account's settings.
+    pub fn validate_user(&self, user: &User, vr: &mut ValidationResults) {
+        if self.disallows_bearer() && user.is_bearer() {
+            vr.add_error("account disallows bearer tokens but user is a bearer token".to_string());
+        }
+    }
+
+    /// Checks that `user`'s permissions stay within the account's policy: every subject the
+    /// user allows must be permitted by the default permissions or a signing key scope, and
+    /// subjects the defaults deny must also be denied for the user. Excess grants are warnings.
+    pub fn user_permissions_within_defaults(&self, user: &User) -> ValidationResults {
+        let mut vr = ValidationResults::new();
+        let policies: Vec<&Permissions> = self
+            .default_permissions
+            .iter()
+            .chain(
+                self.scoped_signing_keys()
+                    .filter_map(|(_, scope)| scope.template.as_ref())
+                    .map(|template| &template.permissions),
+            )
+            .collect();
+        if policies.is_empty() {
+            return vr;
+        }
+
+        let permissions = &user.permissions.permissions;
+        let defaults = self.default_permissions.as_ref();
+        check_user_permission(
+            "publish",
+            &permissions.publish,
+            policies.iter().map(|p| &p.publish),
+            defaults.map(|p| &p.publish),
+            &mut vr,
+        );
+        check_user_permission(
+            "subscribe",
+            &permissions.subscribe,
+            policies.iter().map(|p| &p.subscribe),
+            defaults.map(|p| &p.subscribe),
+            &mut vr,
+        );
+        vr
+    }
+
+    fn disallows_bearer(&self) -> bool {
+        self.account_limits().and_then(|l| l.disallow_bearer) == Some(true)
+    }
+
+    /// Adds a destination for messages published to `from`.
+    pub fn add_mapping(&mut self, from: impl Into<String>, to: WeightedMapping) -> Result<()> {
+        let from = from.into();
+        if !is_valid_subject(&from) {
+            return Err(anyhow::anyhow!("invalid mapping subject {from}"));
+        }
+        self.mappings
+            .get_or_insert_with(BTreeMap::new)
+            .entry(from)
+            .or_default()
+            .push(to);
+        Ok(())
+    }
+
+    /// Replaces all destinations for messages published to `from`.
+    pub fn set_mapping(
+        &mut self,
+        from: impl Into<String>,
+        destinations: Vec<WeightedMapping>,
+    ) -> Result<()> {
+        let from = from.into();
+        if !is_valid_subject(&from) {
+            return Err(anyhow::anyhow!("invalid mapping subject {from}"));
+        }
+        self.mappings
+            .get_or_insert_with(BTreeMap::new)
+            .insert(from, destinations);
+        Ok(())
+    }
+
+    /// Replaces the subjects users may publish to when they don't set their own permissions.
+    pub fn set_default_publish_allow<S: Into<String>>(
+        &mut self,
+        subjects: impl IntoIterator<Item = S>,
+    ) {
+        self.default_permissions_mut().publish.allow =
+            subjects.into_iter().map(Into::into).collect();
+    }
+
+    /// Replaces the subjects users may not publish to when they don't set their own permissions.
+    pub fn set_default_publish_deny<S: Into<String>>(
+        &mut self,
+        subjects: impl IntoIterator<Item = S>,
+    ) {
+        self.default_permissions_mut().publish.deny =
+            subjects.into_iter().map(Into::into).collect();
+    }
+
+    /// Replaces the subjects users may subscribe to when they don't set their own permissions.
+    pub fn set_default_subscribe_allow<S: Into<String>>(
+        &mut self,
+        subjects: impl IntoIterator<Item = S>,
+    ) {
+        self.default_permissions_mut().subscribe.allow =
+            subjects.into_iter().map(Into::into).collect();
+    }
+
+    /// Replaces the subjects users may not subscribe to when they don't set their own
+    /// permissions.
+    pub fn set_default_subscribe_deny<S: Into<String>>(
+        &mut self,
+        subjects: impl IntoIterator<Item = S>,
+    ) {
+        self.default_permissions_mut().subscribe.deny =
+            subjects.into_iter().map(Into::into).collect();
+    }
+
+    fn default_permissions_mut(&mut self) -> &mut Permissions {
+        self.default_permissions
+            .get_or_insert_with(Permissions::default)
+    }
+
+    pub fn has_signing_key(&self, key: &str) -> bool {
+        self.signing_keys
+            .as_ref()
+            .is_some_and(|keys| keys.iter().any(|k| k.key == key))
+    }
+
+    /// Merges `other` into this account's signing keys, keeping the existing order. Keys
+    /// that are already present take the scope of the incoming entry, new keys are appended.
+    pub fn merge_signing_keys(&mut self, other: &IndexSet<SigningKey>) {
+        let keys = self.signing_keys.get_or_insert_with(IndexSet::new);
+        for incoming in other {
+            // `SigningKey` equality covers the scope too, so existing entries are matched by key
+            match keys.iter().position(|k| k.key == incoming.key) {
+                Some(index) => {
+                    keys.shift_remove_index(index);
+                    keys.shift_insert(index, incoming.clone());
+                }
+                None => {
+                    keys.insert(incoming.clone());
+                }
+            }
+        }
+    }
+
+    /// Returns the user scope attached to the signing key `key`, if it is a scoped key.
+    pub fn scope_for_signing_key(&self, key: &str) -> Option<&UserScope> {
+        self.signing_keys
+            .iter()
+            .flatten()
+            .find(|k| k.key == key)
+            .and_then(|k| k.scope.as_ref())
+    }
+
+    /// Warns about imports and exports that allow tracing while the account has no trace
+    /// configuration, since nats-server only traces messages for accounts that enable it.
+    pub fn validate_trace_consistency(&self, vr: &mut ValidationResults) {
+        if self.trace.is_some() {
+            return;
+        }
+        for import in self.imports_iter() {
+            if import.allow_trace == Some(true) {
+                vr.add_warning(format!(
+                    "import {} allows tracing, but the account has no trace configuration",
+                    import.subject
+                ));
+            }
+        }
+        for export in self.exports_iter() {
+            if export.allow_trace == Some(true) {
+                vr.add_warning(format!(
+                    "export {} allows tracing, but the account has no trace configuration",
+                    export.subject
+                ));
+            }
+        }
+    }
+
+    /// Warns about exports that users relying on the account's default permissions can't
+    /// use: serving a service export needs subscribe access, feeding a stream export needs
+    /// publish access.
+    pub fn validate_default_permissions_cover_exports(&self, vr: &mut ValidationResults) {
+        let Some(defaults) = &self.default_permissions else {
+            return;
+        };
+        for export in self.exports_iter() {
+            let (permission, action) = match export.export_type.clone().unwrap_or_default() {
+                ExportType::Service => (&defaults.subscribe, "subscribe to"),
+                ExportType::Stream => (&defaults.publish, "publish to"),
+                ExportType::Unknown => continue,
+            };
+            if !permission.allows(&export.subject) {
+                vr.add_warning(format!(
+                    "default permissions do not allow users to {action} export {}",
+                    export.subject
+                ));
+            }
+        }
+    }
+
+    /// Warns about exports of the same type where one subject contains the other, since it is
+    /// ambiguous which export an import resolves to.
+    pub fn validate_export_overlap(&self, vr: &mut ValidationResults) {
+        let exports: Vec<&Export> = self.exports_iter().collect();
+        for (i, a) in exports.iter().enumerate() {
+            for b in &exports[i + 1..] {
+                if a.export_type.clone().unwrap_or_default()
+                    == b.export_type.clone().unwrap_or_default()
+                    && (subject_is_subset(&a.subject, &b.subject)
+                        || subject_is_subset(&b.subject, &a.subject))
+                {
+                    vr.add_warning(format!(
+                        "exports {} and {} have overlapping subjects",
+                        a.subject, b.subject
+                    ));
+                }
+            }
+        }
+    }
+
+    /// Validates each scoped signing key's template like the permissions of an issued user,
+    /// qualifying the issues with the scope's key and role.
+    pub fn validate_scope_templates(&self, vr: &mut ValidationResults) {
+        for (key, scope) in self.scoped_signing_keys() {
+            let Some(template) = &scope.template else {
+                continue;
+            };
+            let mut scope_vr = ValidationResults::new();
+            template.validate(&mut scope_vr);
+            let label = match &scope.role {
+                Some(role) => format!("scope {key} (role {role})"),
+                None => format!("scope {key}"),
+            };
+            for issue in scope_vr.issues() {
+                vr.add_issue(ValidationIssue {
+                    description: format!("{label}: {}", issue.description),
+                    ..issue.clone()
+                });
+            }
+        }
+    }
+
+    /// Returns the first user scope with the given `role`.
+    pub fn scope_for_role(&self, role: &str) -> Option<&UserScope> {
+        self.signing_keys
+            .iter()
+            .flatten()
+            .filter_map(|k| k.scope.as_ref())
+            .find(|scope| scope.role.as_deref() == Some(role))
+    }
+
+    /// Signs `user` on behalf of the account identified by `account_key`, using either the
+    /// account key itself or one of its signing keys.
+    ///
+    /// When `signing_key` is a scoped signing key, the user's permissions are replaced with the
+    /// scope's template, since nats-server ignores the user's own permissions in that case.
+    /// Bearer users are rejected if the account disallows them.
+    pub fn issue_user(
+        &self,
+        account_key: &KeyPair,
+        signing_key: &KeyPair,
+        user: &mut Claims<User>,
+    ) -> Result<String> {
+        let account_public = account_key.public_key();
+        let signing_public = signing_key.public_key();
+        let delegated = signing_public != account_public;
+        if delegated && !self.has_signing_key(&signing_public) {
+            return Err(anyhow::anyhow!(
+                "{signing_public} is not a signing key of this account"
+            ));
+        }
+        let scope = delegated
+            .then(|| self.scope_for_signing_key(&signing_public))
+            .flatten();
+
+        // check the permissions the user will be issued with before touching `user`
+        let is_bearer = match scope {
+            Some(scope) => scope
+                .template
+                .as_ref()
+                .is_some_and(|t| t.bearer_token == Some(true)),
+            None => user.nats.is_bearer(),
+        };
+        if self.disallows_bearer() && is_bearer {
+            return Err(anyhow::anyhow!(
+                "account disallows bearer tokens, so user {} cannot be issued as one",
+                user.sub
+            ));
+        }
+
+        if let Some(scope) = scope {
+            user.nats.permissions = scope.template.clone().unwrap_or_default();
+        }
+        if delegated {
+            user.nats.issuer_account = Some(account_public);
+        }
+
+        Ok(user.encode(signing_key)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{types::ExportType, user::UserPermissionLimits};
+
+    fn account_disallowing_bearer(disallow_bearer: Option<bool>) -> Account {
+        let mut account = Account::default();
+        account
+            .limits
+            .as_mut()
+            .unwrap()
+            .account
+            .as_mut()
+            .unwrap()
+            .disallow_bearer = disallow_bearer;
+        account
+    }
+
+    fn bearer_user() -> User {
+        let mut user = User::default();
+        user.permissions.bearer_token = Some(true);
+        user
+    }
+
+    #[test]
+    fn test_add_import_export() {
+        let mut account = Account::default();
+        assert_eq!(account.imports_iter().count(), 0);
+        assert_eq!(account.exports_iter().count(), 0);
+
+        account.add_export(Export {
+            subject: "events.>".to_string(),
+            export_type: Some(ExportType::Stream),
+            ..Default::default()
+        });
+        account.add_import(Import {
+            subject: "svc.orders".to_string(),
+            account: "ACVUKSAVDJV65AZLNRPSKFJPY22WNRZIXFUORXXKVY2LHXM2JMKL7G4F".to_string(),
+            export_type: Some(ExportType::Service),
+            ..Default::default()
+        });
+
+        assert_eq!(account.exports_iter().count(), 1);
+        assert_eq!(account.imports_iter().next().unwrap().subject, "svc.orders");
+
+        let json = serde_json::to_value(&account).unwrap();
+        assert_eq!(json["exports"][0]["type"], "stream");
+        assert_eq!(json["imports"][0]["type"], "service");
+    }
+
+    #[test]
+    fn test_operator_limits_round_trip() {
+        let json = serde_json::json!({
+            "subs": 100,
+            "data": 1048576,
+            "payload": 65536,
+            "imports": 10,
+            "exports": 20,
+            "wildcards": false,
+            "disallow_bearer": true,
+            "conn": 50,
+            "leaf": 5,
+            "mem_storage": 1073741824,
+            "disk_storage": 10737418240i64,
+            "streams": 10,
+            "consumer": 100,
+            "max_ack_pending": 1000,
+            "max_bytes_required": true,
+            "tiered_limits": {
+                "R1": {"mem_storage": 1024, "disk_storage": 2048, "streams": 1},
+                "R3": {"mem_storage": 4096, "disk_storage": 8192, "streams": 3, "consumer": -1}
+            }
+        });
+
+        let limits: OperatorLimits = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(limits.nats.as_ref().unwrap().payload, Some(65536));
+        assert_eq!(limits.account.as_ref().unwrap().conn, Some(50));
+        assert_eq!(limits.jetstream.as_ref().unwrap().streams, Some(10));
+        let tiers = limits.tiered_limits.as_ref().unwrap();
+        assert_eq!(tiers["R3"].consumer, Some(-1));
+        assert_eq!(serde_json::to_value(&limits).unwrap(), json);
+    }
+
+    #[test]
+    fn test_connection_limits() {
+        let limits = AccountLimits::default();
+        assert_eq!(limits.max_connections(), None);
+        assert_eq!(limits.max_leafnodes(), None);
+
+        let limits = AccountLimits {
+            conn: Some(100),
+            leaf: Some(0),
+            ..Default::default()
+        };
+        assert_eq!(limits.max_connections(), Some(100));
+        assert_eq!(limits.max_leafnodes(), Some(0));
+        let mut vr = ValidationResults::new();
+        limits.validate(&mut vr);
+        assert!(vr.is_empty());
+
+        let limits = AccountLimits {
+            conn: Some(-5),
+            ..Default::default()
+        };
+        assert_eq!(limits.max_connections(), Some(0));
+        let mut vr = ValidationResults::new();
+        limits.validate(&mut vr);
+        assert!(vr.is_blocking(false));
+    }
+
+    fn account_with_imports(count: usize, limit: i64) -> Claims<Account> {
+        let mut claims = Account::new_claims("test".to_string(), "ABC".to_string());
+        for i in 0..count {
+            claims.nats.add_import(Import {
+                subject: format!("svc.{i}"),
+                ..Default::default()
+            });
+        }
+        claims
+            .nats
+            .limits
+            .as_mut()
+            .unwrap()
+            .account
+            .as_mut()
+            .unwrap()
+            .imports = Some(limit);
+        claims
+    }
+
+    #[test]
+    fn test_import_limit_exceeded() {
+        let mut vr = ValidationResults::new();
+        account_with_imports(3, 2).validate(&mut vr);
+        assert!(vr.is_blocking(false));
+    }
+
+    #[test]
+    fn test_import_limit_unlimited() {
+        let mut vr = ValidationResults::new();
+        account_with_imports(3, NO_LIMIT).validate(&mut vr);
+        assert!(!vr.is_blocking(false));
+
+        let mut vr = ValidationResults::new();
+        account_with_imports(2, 2).validate(&mut vr);
+        assert!(!vr.is_blocking(false));
+    }
+
+    fn account_with_export(subject: &str) -> Claims<Account> {
+        let mut claims = Account::new_claims("test".to_string(), "ABC".to_string());
+        claims.nats.add_export(Export {
+            subject: subject.to_string(),
+            ..Default::default()
+        });
+        claims
+            .nats
+            .limits
+            .as_mut()
+            .unwrap()
+            .account
+            .as_mut()
+            .unwrap()
+            .wildcard_exports = Some(false);
+        claims
+    }
+
+    #[test]
+    fn test_wildcard_export_disallowed() {
+        let mut vr = ValidationResults::new();
+        account_with_export("foo.>").validate(&mut vr);
+        assert!(vr.is_blocking(false));
+    }
+
+    #[test]
+    fn test_plain_export_allowed() {
+        let mut vr = ValidationResults::new();
+        account_with_export("foo.bar").validate(&mut vr);
+        assert!(!vr.is_blocking(false));
+    }
+
+    fn account_with_trace(destination: &str, sampling: u64) -> Claims<Account> {
+        let mut claims = Account::new_claims("test".to_string(), "ABC".to_string());
+        claims.nats.trace = Some(MsgTrace {
+            destination: Some(destination.to_string()),
+            sampling: Some(sampling),
+        });
+        claims
+    }
+
+    #[test]
+    fn test_trace_consistency() {
+        let export = Export {
+            subject: "foo".to_string(),
+            export_type: Some(ExportType::Stream),
+            allow_trace: Some(true),
+            ..Default::default()
+        };
+        let mut claims = Account::new_claims("test".to_string(), "ABC".to_string());
+        claims.nats.add_export(export.clone());
+        let mut vr = ValidationResults::new();
+        claims.nats.validate_trace_consistency(&mut vr);
+        assert!(!vr.is_empty());
+        assert!(!vr.is_blocking(false));
+
+        let mut claims = account_with_trace("trace.events", 50);
+        claims.nats.add_export(export);
+        let mut vr = ValidationResults::new();
+        claims.nats.validate_trace_consistency(&mut vr);
+        assert!(vr.is_empty());
+    }
+
+    #[test]
+    fn test_default_permissions_cover_exports() {
+        let mut account = Account {
+            default_permissions: Some(Permissions::deny_all()),
+            ..Default::default()
+        };
+        account.add_export(Export {
+            subject: "svc.>".to_string(),
+            export_type: Some(ExportType::Service),
+            ..Default::default()
+        });
+        let mut vr = ValidationResults::new();
+        account.validate_default_permissions_cover_exports(&mut vr);
+        assert_eq!(vr.issues().count(), 1);
+        assert!(!vr.is_blocking(false));
+
+        account.default_permissions = Some(Permissions {
+            subscribe: Permission {
+                allow: vec![">".to_string()],
+                deny: Vec::new(),
+            },
+            ..Permissions::deny_all()
+        });
+        let mut vr = ValidationResults::new();
+        account.validate_default_permissions_cover_exports(&mut vr);
+        assert!(vr.is_empty());
+    }
+
+    #[test]
+    fn test_user_permissions_within_defaults() {
+        let mut account = Account::default();
+        account.set_default_publish_allow(["orders.>"]);
+        account.set_default_publish_deny(["orders.admin.>"]);
+
+        let mut user = User::default();
+        user.permissions.permissions.publish.allow = vec!["orders.new".to_string()];
+        assert!(account.user_permissions_within_defaults(&user).is_empty());
+
+        user.permissions.permissions.publish.allow = vec!["secret.>".to_string()];
+        let vr = account.user_permissions_within_defaults(&user);
+        assert_eq!(vr.issues().count(), 1);
+        assert!(!vr.is_blocking(false));
+
+        user.permissions.permissions.publish.allow = vec!["orders.>".to_string()];
+        assert!(!account.user_permissions_within_defaults(&user).is_empty());
+        user.permissions.permissions.publish.deny = vec!["orders.admin.>".to_string()];
+        assert!(account.user_permissions_within_defaults(&user).is_empty());
+    }
+
+    #[test]
+    fn test_export_overlap() {
+        let account_with_exports = |subjects: [&str; 2]| {
+            let mut account = Account::default();
+            for subject in subjects {
+                account.add_export(Export {
+                    subject: subject.to_string(),
+                    export_type: Some(ExportType::Service),
+                    ..Default::default()
+                });
+            }
+            let mut vr = ValidationResults::new();
+            account.validate_export_overlap(&mut vr);
+            vr
+        };
+
+        let vr = account_with_exports(["svc.>", "svc.foo"]);
+        assert_eq!(vr.issues().count(), 1);
+        assert!(!vr.is_blocking(false));
+        assert!(account_with_exports(["a.>", "b.>"]).is_empty());
+    }
+
+    #[test]
+    fn test_scope_template_validation() {
+        let mut template = UserPermissionLimits::default();
+        template.permissions.publish.allow = vec!["orders.>".to_string()];
+        template.permissions.subscribe.allow = vec!["_INBOX.> workers".to_string()];
+        let (_, valid_key, account) = account_with_scoped_key(template.clone());
+        let mut vr = ValidationResults::new();
+        account.validate_scope_templates(&mut vr);
+        assert!(vr.is_empty());
+
+        template.permissions.publish.allow = vec!["orders..>".to_string()];
+        let (_, invalid_key, account) = account_with_scoped_key(template);
+        let mut vr = ValidationResults::new();
+        account.validate_scope_templates(&mut vr);
+        assert!(vr.is_blocking(false));
+        let issue = vr.issues().next().unwrap();
+        assert!(issue.description.contains(&invalid_key.public_key()));
+        assert!(!issue.description.contains(&valid_key.public_key()));
+    }
+
+    #[test]
+    fn test_display_summary() {
+        let key = KeyPair::new_account();
+        let (_, _, account) = account_with_scoped_key(UserPermissionLimits::default());
+        let mut claims = Account::new_claims("orders".to_string(), key.public_key());
+        claims.nats = account;
+        let summary = claims.to_string();
+        assert!(summary.contains("orders"));
+        assert!(summary.contains(&key.public_key()));
+        assert!(summary.contains("1 (1 scoped)"));
+
+        claims.nats.limits = Some(OperatorLimits {
+            account: Some(AccountLimits {
+                conn: Some(-5),
+                ..Default::default()
+            }),
+            ..Default::default()
+        });
+        let summary = claims.to_string();
+        assert!(summary.contains("Connections:    0"));
+        assert!(!summary.contains("unlimited"));
+    }
+
+    #[test]
+    fn test_trace_validation() {
+        let mut vr = ValidationResults::new();
+        account_with_trace("trace.events", 50).validate(&mut vr);
+        assert!(vr.is_empty());
+
+        let mut vr = ValidationResults::new();
+        account_with_trace("trace.events", 150).validate(&mut vr);
+        assert!(vr.is_blocking(false));
+
+        let mut vr = ValidationResults::new();
+        account_with_trace("trace.>", 50).validate(&mut vr);
+        assert!(vr.is_blocking(false));
+
+        let mut vr = ValidationResults::new();
+        account_with_trace("trace events", 50).validate(&mut vr);
+        assert!(vr.is_blocking(false));
+    }
+
+    #[test]
+    fn test_set_trace_destination() {
+        let mut trace = MsgTrace::default();
+        assert!(trace.set_destination("_TRACE.*").is_err());
+        assert!(trace.set_destination("_TRACE.>").is_err());
+        assert!(trace.destination.is_none());
+
+        trace.set_destination("_TRACE.events").unwrap();
+        assert_eq!(trace.destination.as_deref(), Some("_TRACE.events"));
+    }
+
+    fn external_authorization(
+        auth_users: &[&str],
+        allowed_accounts: &[&str],
+        xkey: Option<String>,
+    ) -> ValidationResults {
+        let authorization = ExternalAuthorization {
+            auth_users: Some(auth_users.iter().map(|u| u.to_string()).collect()),
+            allowed_accounts: Some(allowed_accounts.iter().map(|a| a.to_string()).collect()),
+            xkey,
+        };
+        let mut vr = ValidationResults::new();
+        authorization.validate(&mut vr);
+        vr
+    }
+
+    #[test]
+    fn test_external_authorization_valid() {
+        let user = KeyPair::new_user().public_key();
+        let account = KeyPair::new_account().public_key();
+        let xkey = nkeys::XKey::new().public_key();
+
+        assert!(external_authorization(&[&user], &[&account], Some(xkey)).is_empty());
+        assert!(external_authorization(&[&user], &["*"], None).is_empty());
+    }
+
+    #[test]
+    fn test_external_authorization_invalid() {
+        let user = KeyPair::new_user().public_key();
+        let account = KeyPair::new_account().public_key();
+
+        let vr = external_authorization(&[&account], &[], None);
+        assert!(vr.is_blocking(false));
+
+        let vr = external_authorization(&[&user], &[&user], None);
+        assert!(vr.is_blocking(false));
+
+        let vr = external_authorization(&[&user], &["*", &account], None);
+        assert!(vr.is_blocking(false));
+
+        let vr = external_authorization(&[&user], &[], Some(user.clone()));
+        assert!(vr.is_blocking(false));
+    }
+
+    #[test]
+    fn test_external_authorization_in_account() {
+        let mut claims = Account::new_claims("test".to_string(), "ABC".to_string());
+        claims.nats.authorization = Some(ExternalAuthorization {
+            auth_users: Some(BTreeSet::from(["not a key".to_string()])),
+            allowed_accounts: None,
+            xkey: None,
+        });
+        let mut vr = ValidationResults::new();
+        claims.validate(&mut vr);
+        assert!(vr.is_blocking(false));
+    }
+
+    #[test]
+    fn test_encoded_len_warning() {
+        let account_key = KeyPair::new_account();
+        let mut claims = Account::new_claims("test".to_string(), account_key.public_key());
+        let mut vr = ValidationResults::new();
+        claims.validate(&mut vr);
+        assert!(vr.is_empty());
+
+        let export = Export {
+            subject: "x".repeat(DEFAULT_MAX_ACCOUNT_JWT_LEN),
+            export_type: Some(ExportType::Stream),
+            ..Default::default()
+        };
+        claims.nats.add_export(export);
+        let mut vr = ValidationResults::new();
+        claims.validate(&mut vr);
+        assert!(!vr.is_empty());
+        assert!(!vr.is_blocking(false));
+
+        let mut vr = ValidationResults::new();
+        claims.validate_encoded_len(usize::MAX, &mut vr);
+        assert!(vr.is_empty());
+
+        let len = claims.encoded_len(&account_key).unwrap();
+        assert!(len > DEFAULT_MAX_ACCOUNT_JWT_LEN);
+        assert!(len >= claims.estimated_encoded_len().unwrap());
+    }
+
+    #[test]
+    fn test_bearer_user_disallowed() {
+        let mut vr = ValidationResults::new();
+        account_disallowing_bearer(Some(true)).validate_user(&bearer_user(), &mut vr);
+        assert!(vr.is_blocking(false));
+    }
+
+    #[test]
+    fn test_issue_bearer_user_disallowed() {
+        let account_key = KeyPair::new_account();
+        let mut user = User::new_claims("user".to_string(), KeyPair::new_user().public_key());
+        user.nats = bearer_user();
+
+        assert!(account_disallowing_bearer(Some(true))
+            .issue_user(&account_key, &account_key, &mut user)
+            .is_err());
+        assert!(account_disallowing_bearer(None)
+            .issue_user(&account_key, &account_key, &mut user)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_issue_bearer_user_disallowed_leaves_user_unchanged() {
+        let (account_key, scoped_key, mut account) =
+            account_with_scoped_key(UserPermissionLimits {
+                bearer_token: Some(true),
+                ..Default::default()
+            });
+        account.limits = account_disallowing_bearer(Some(true)).limits;
+
+        let mut user = User::new_claims("user".to_string(), KeyPair::new_user().public_key());
+        user.nats.permissions.permissions.publish.allow = vec![">".to_string()];
+        let before = user.nats.clone();
+
+        assert!(account
+            .issue_user(&account_key, &scoped_key, &mut user)
+            .is_err());
+        assert_eq!(user.nats.issuer_account, before.issuer_account);
+        assert_eq!(user.nats.permissions, before.permissions);
+    }
+
+    fn account_with_scoped_key(template: UserPermissionLimits) -> (KeyPair, KeyPair, Account) {
+        let account_key = KeyPair::new_account();
+        let scoped_key = KeyPair::new_account();
+        let account = Account {
+            signing_keys: Some(IndexSet::from([SigningKey {
+                key: scoped_key.public_key(),
+                scope: Some(UserScope {
+                    key: scoped_key.public_key(),
+                    template: Some(template),
+                    ..Default::default()
+                }),
+            }])),
+            ..Default::default()
+        };
+        (account_key, scoped_key, account)
+    }
+
+    #[test]
+    fn test_scoped_signing_keys() {
+        let (_, scoped_key, mut account) = account_with_scoped_key(UserPermissionLimits::default());
+        let plain_key = KeyPair::new_account().public_key();
+        account.signing_keys.as_mut().unwrap().insert(SigningKey {
+            key: plain_key,
+            scope: None,
+        });
+
+        assert_eq!(account.signing_keys_iter().count(), 2);
+        let scoped: Vec<_> = account.scoped_signing_keys().collect();
+        assert_eq!(scoped.len(), 1);
+        assert_eq!(scoped[0].0, scoped_key.public_key());
+        assert_eq!(scoped[0].1.key, scoped_key.public_key());
+    }
+
+    #[test]
+    fn test_merge_signing_keys() {
+        let (_, scoped_key, mut base) = account_with_scoped_key(UserPermissionLimits::default());
+        let plain_key = KeyPair::new_account().public_key();
+        base.signing_keys.as_mut().unwrap().insert(SigningKey {
+            key: plain_key.clone(),
+            scope: None,
+        });
+        let new_key = KeyPair::new_account().public_key();
+
+        let scope = UserScope {
+            key: plain_key.clone(),
+            role: Some("admin".to_string()),
+            ..Default::default()
+        };
+        base.merge_signing_keys(&IndexSet::from([
+            SigningKey {
+                key: plain_key.clone(),
+                scope: Some(scope.clone()),
+            },
+            SigningKey {
+                key: new_key.clone(),
+                scope: None,
+            },
+        ]));
+
+        let keys: Vec<_> = base.signing_keys_iter().map(|k| k.key.as_str()).collect();
+        assert_eq!(
+            keys,
+            [
+                scoped_key.public_key().as_str(),
+                plain_key.as_str(),
+                new_key.as_str()
+            ]
+        );
+        assert_eq!(base.scope_for_signing_key(&plain_key), Some(&scope));
+        assert!(base
+            .scope_for_signing_key(&scoped_key.public_key())
+            .is_some());
+    }
+
+    #[test]
+    fn test_issue_user_applies_scope_template() {
+        let mut template = UserPermissionLimits::default();
+        template.permissions.publish.allow = vec!["orders.>".to_string()];
+        let (account_key, scoped_key, account) = account_with_scoped_key(template.clone());
+
+        let mut user = User::new_claims("user".to_string(), KeyPair::new_user().public_key());
+        user.nats.permissions.permissions.publish.allow = vec![">".to_string()];
+        user.nats.permissions.bearer_token = Some(true);
+        let jwt = account
+            .issue_user(&account_key, &scoped_key, &mut user)
+            .unwrap();
+
+        let decoded = Claims::<User>::decode(&jwt).unwrap();
+        assert_eq!(decoded.iss, scoped_key.public_key());
+        assert_eq!(decoded.nats.issuer_account, Some(account_key.public_key()));
+        assert_eq!(decoded.nats.permissions, template);
+    }
+
+    #[test]
+    fn test_scope_lookup() {
+        let scoped = |role: &str| {
+            let key = KeyPair::new_account().public_key();
+            SigningKey {
+                key: key.clone(),
+                scope: Some(UserScope {
+                    key,
+                    role: Some(role.to_string()),
+                    ..Default::default()
+                }),
+            }
+        };
+        let admin = scoped("admin");
+        let readonly = scoped("readonly");
+        let unscoped = SigningKey {
+            key: KeyPair::new_account().public_key(),
+            scope: None,
+        };
+        let account = Account {
+            signing_keys: Some(IndexSet::from([
+                admin.clone(),
+                readonly.clone(),
+                unscoped.clone(),
+            ])),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            account.scope_for_signing_key(&admin.key),
+            admin.scope.as_ref()
+        );
+        assert_eq!(
+            account.scope_for_signing_key(&readonly.key),
+            readonly.scope.as_ref()
+        );
+        assert_eq!(account.scope_for_signing_key(&unscoped.key), None);
+        assert_eq!(account.scope_for_role("admin").unwrap().key, admin.key);
+        assert_eq!(
+            account.scope_for_role("readonly").unwrap().key,
+            readonly.key
+        );
+        assert_eq!(account.scope_for_role("writer"), None);
+    }
+
+    #[test]
+    fn test_issue_user_with_account_key() {
+        let (account_key, _, account) = account_with_scoped_key(UserPermissionLimits::default());
+        let mut user = User::new_claims("user".to_string(), KeyPair::new_user().public_key());
+        user.nats.permissions.permissions.publish.allow = vec![">".to_string()];
+        let jwt = account
+            .issue_user(&account_key, &account_key, &mut user)
+            .unwrap();
+
+        let decoded = Claims::<User>::decode(&jwt).unwrap();
+        assert_eq!(decoded.iss, account_key.public_key());
+        assert_eq!(decoded.nats.issuer_account, None);
+        assert_eq!(
+            decoded.nats.permissions.permissions.publish.allow,
+            vec![">"]
+        );
+
+        assert!(account
+            .issue_user(&account_key, &KeyPair::new_account(), &mut user)
+            .is_err());
+    }
+
+    fn mapping(subject: &str) -> WeightedMapping {
+        WeightedMapping {
+            subject: subject.to_string(),
+            weight: None,
+            cluster: None,
+        }
+    }
+
+    #[test]
+    fn test_add_mapping() {
+        let mut account = Account::default();
+        let weighted = |subject: &str, weight| WeightedMapping {
+            weight: Some(weight),
+            ..mapping(subject)
+        };
+        account
+            .add_mapping("orders.*", weighted("orders.east.{{wildcard(1)}}", 60))
+            .unwrap();
+        account
+            .add_mapping("orders.*", weighted("orders.west.{{wildcard(1)}}", 40))
+            .unwrap();
+        assert!(account.add_mapping("orders..bad", mapping("x")).is_err());
+
+        let json = serde_json::to_value(&account).unwrap();
+        assert_eq!(
+            json["mappings"],
+            serde_json::json!({
+                "orders.*": [
+                    { "subject": "orders.east.{{wildcard(1)}}", "weight": 60 },
+                    { "subject": "orders.west.{{wildcard(1)}}", "weight": 40 },
+                ]
+            })
+        );
+
+        account
+            .set_mapping("orders.*", vec![mapping("orders.all")])
+            .unwrap();
+        assert_eq!(account.mappings.as_ref().unwrap()["orders.*"].len(), 1);
+    }
+
+    #[test]
+    fn test_validate_transform() {
+        assert!(mapping("bar.{{wildcard(1)}}")
+            .validate_transform("foo.*")
+            .is_ok());
+        assert!(mapping("bar.{{ wildcard(2) }}.{{partition(10,1,2)}}")
+            .validate_transform("foo.*.*")
+            .is_ok());
+        assert!(mapping("bar.baz").validate_transform("foo.>").is_ok());
+        assert!(mapping("bar.{{left(1,2)}}.{{right(2,3)}}")
+            .validate_transform("foo.*.*")
+            .is_ok());
+        assert!(mapping("bar.{{partition(10)}}")
+            .validate_transform("foo.>")
+            .is_ok());
+        assert!(mapping("bar.{{random(5)}}")
+            .validate_transform("foo")
+            .is_ok());
+    }
+
+    #[test]
+    fn test_validate_transform_missing_wildcard() {
+        assert!(mapping("bar.{{wildcard(2)}}")
+            .validate_transform("foo.*")
+            .is_err());
+        assert!(mapping("bar.{{partition(10,1,2)}}")
+            .validate_transform("foo.*")
+            .is_err());
+        assert!(mapping("bar.{{wildcard(0)}}")
+            .validate_transform("foo.*")
+            .is_err());
+        assert!(mapping("bar.{{unknown(1)}}")
+            .validate_transform("foo.*")
+            .is_err());
+        assert!(mapping("bar.{{wildcard(1)")
+            .validate_transform("foo.*")
+            .is_err());
+        assert!(mapping("bar.{{left(2,1)}}")
+            .validate_transform("foo.*")
+            .is_err());
+        assert!(mapping("bar.{{right(2,1)}}")
+            .validate_transform("foo.*")
+            .is_err());
+    }
+
+    #[test]
+    fn test_set_default_permissions() {
+        let mut account = Account {
+            default_permissions: None,
+            ..Default::default()
+        };
+        account.set_default_publish_allow(["orders.>", "events.>"]);
+        account.set_default_subscribe_deny(vec!["_INBOX.>".to_string()]);
+
+        let json = serde_json::to_value(&account).unwrap();
+        assert_eq!(
+            json["default_permissions"],
+            serde_json::json!({
+                "pub": { "allow": ["orders.>", "events.>"] },
+                "sub": { "deny": ["_INBOX.>"] },
+            })
+        );
+    }
+
+    #[test]
+    fn test_bearer_user_allowed() {
+        let mut vr = ValidationResults::new();
+        account_disallowing_bearer(None).validate_user(&bearer_user(), &mut vr);
+        assert!(!vr.is_blocking(false));
+
+        let mut vr = ValidationResults::new();
+        account_disallowing_bearer(Some(true)).validate_user(&User::default(), &mut vr);
+        assert!(!vr.is_blocking(false));
+    }
 }
@@ -1,18 +1,22 @@
 use crate::{
     types::{ExportType, GenericFields},
-    Claim, ClaimType, Claims,
+    validation::{is_valid_public_key, ValidationResults},
+    Claim, ClaimType, Claims, ClaimsBuilder,
 };
 use data_encoding::BASE32_NOPAD;
+use nkeys::{KeyPair, KeyPairType};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha512_256};
+use std::time::Duration;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Activation {
-    #[serde(skip_serializing_if = "String::is_empty")]
+    #[serde(rename = "subject", default, skip_serializing_if = "String::is_empty")]
     pub import_subject: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "kind", skip_serializing_if = "Option::is_none")]
     pub import_type: Option<ExportType>,
-    #[serde(skip_serializing_if = "String::is_empty")]
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub issuer_account: String,
 
     #[serde(flatten)]
@@ -20,7 +24,13 @@ pub struct Activation {
 }
 
 impl Claim for Activation {
-    fn validate() {}
+    const CLAIM_TYPE: ClaimType = ClaimType::Activation;
+
+    fn validate(_claims: &Claims<Self>, _vr: &mut ValidationResults) {}
+
+    fn generic_fields(&self) -> Option<&GenericFields> {
+        Some(&self.generic_fields)
+    }
 }
 
 impl Default for Activation {
@@ -30,7 +40,7 @@ impl Default for Activation {
             import_type: None,
             issuer_account: "".to_string(),
             generic_fields: GenericFields {
-                claim_type: ClaimType::Activation,
+                claim_type: Self::CLAIM_TYPE,
                 ..Default::default()
             },
         }
@@ -46,6 +56,41 @@ impl Activation {
         claim
     }
 
+    /// Issues an activation granting `target_account` access to `import_subject`, signed by
+    /// `account_key` and expiring after `ttl` if given.
+    ///
+    /// `issuer_account` is taken from `self` when set, as it must be when `account_key` is a
+    /// signing key of the exporting account, and otherwise defaults to `account_key`.
+    pub fn issue(
+        &self,
+        account_key: &KeyPair,
+        import_subject: &str,
+        target_account: &str,
+        ttl: Option<Duration>,
+    ) -> anyhow::Result<String> {
+        let signer = account_key.public_key();
+        if account_key.key_pair_type() != KeyPairType::Account {
+            return Err(anyhow::anyhow!("{signer} is not an account key"));
+        }
+        if !is_valid_public_key(target_account, KeyPairType::Account) {
+            return Err(anyhow::anyhow!(
+                "{target_account} is not a valid account public key"
+            ));
+        }
+
+        let mut activation = self.clone();
+        activation.import_subject = import_subject.to_string();
+        if activation.issuer_account.is_empty() {
+            activation.issuer_account = signer;
+        }
+
+        let mut builder = ClaimsBuilder::new(activation).subject(target_account);
+        if let Some(ttl) = ttl {
+            builder = builder.expires_in(ttl);
+        }
+        Ok(builder.build().encode(account_key)?)
+    }
+
     pub fn hash(claims: Claims<Activation>) -> anyhow::Result<String> {
         if claims.iss.is_empty() || claims.sub.is_empty() || claims.nats.import_subject.is_empty() {
             return Err(anyhow::anyhow!("not enough data in the claim to hash"));
@@ -83,3 +128,45 @@ impl Activation {
         cleaned
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_issue() {
+        let account_key = KeyPair::new_account();
+        let target = KeyPair::new_account().public_key();
+        let activation = Activation {
+            import_type: Some(ExportType::Service),
+            ..Default::default()
+        };
+
+        let token = activation
+            .issue(
+                &account_key,
+                "svc.orders",
+                &target,
+                Some(Duration::from_secs(3600)),
+            )
+            .unwrap();
+        let claims = Claims::<Activation>::decode_checked(&token).unwrap();
+        assert_eq!(claims.sub, target);
+        assert_eq!(claims.iss, account_key.public_key());
+        assert_eq!(claims.nats.issuer_account, account_key.public_key());
+        assert_eq!(claims.nats.import_subject, "svc.orders");
+        assert!(claims.exp.unwrap() > claims.iat as i64 + 3590);
+
+        let token = activation
+            .issue(&account_key, "svc.orders", &target, None)
+            .unwrap();
+        assert_eq!(Claims::<Activation>::decode(&token).unwrap().exp, None);
+
+        assert!(activation
+            .issue(&KeyPair::new_user(), "svc.orders", &target, None)
+            .is_err());
+        assert!(activation
+            .issue(&account_key, "svc.orders", "not a key", None)
+            .is_err());
+    }
+}
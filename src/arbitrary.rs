@@ -0,0 +1,388 @@
+use crate::{
+    account::{Account, AccountLimits, JetStreamLimits, OperatorLimits},
+    operator::Operator,
+    types::{
+        Export, ExportType, Import, Limits, NatsLimits, Permission, Permissions,
+        ResponsePermission, ResponseType, ScopeType, SigningKey, UserScope, NO_LIMIT,
+    },
+    user::{ConnectionType, User, UserPermissionLimits},
+    Claim, Claims, ClaimsBuilder,
+};
+use indexmap::IndexSet;
+use nkeys::{KeyPair, KeyPairType};
+use proptest::{
+    collection::{btree_map, vec},
+    option,
+    prelude::*,
+};
+use std::time::Duration;
+
+fn public_key(kind: KeyPairType) -> impl Strategy<Value = String> {
+    any::<[u8; 32]>().prop_map(move |seed| {
+        KeyPair::new_from_raw(kind.clone(), seed)
+            .expect("any 32 bytes are a valid seed")
+            .public_key()
+    })
+}
+
+fn subject() -> impl Strategy<Value = String> {
+    "[a-z0-9_-]{1,8}(\\.[a-z0-9_-]{1,8}){0,3}(\\.[*>])?"
+}
+
+// unicode text, including characters Go escapes such as `<`, `>` and `&`
+fn text() -> impl Strategy<Value = String> {
+    "\\PC{0,24}"
+}
+
+fn tags() -> impl Strategy<Value = Option<Vec<String>>> {
+    option::of(vec("[a-z0-9:-]{1,12}", 1..4))
+}
+
+fn limit() -> impl Strategy<Value = Option<i64>> {
+    option::of(prop_oneof![Just(NO_LIMIT), 0..i64::MAX])
+}
+
+fn duration() -> impl Strategy<Value = Duration> {
+    (0u64..100_000_000).prop_map(Duration::from_millis)
+}
+
+fn permission() -> impl Strategy<Value = Permission> {
+    (vec(subject(), 0..3), vec(subject(), 0..3))
+        .prop_map(|(allow, deny)| Permission { allow, deny })
+}
+
+fn permissions() -> impl Strategy<Value = Permissions> {
+    (
+        permission(),
+        permission(),
+        option::of((any::<i64>(), duration())),
+    )
+        .prop_map(|(publish, subscribe, resp)| Permissions {
+            publish,
+            subscribe,
+            resp: resp.map(|(max_messages, ttl)| ResponsePermission { max_messages, ttl }),
+        })
+}
+
+fn nats_limits() -> impl Strategy<Value = NatsLimits> {
+    (limit(), limit(), limit()).prop_map(|(subs, data, payload)| NatsLimits {
+        subs,
+        data,
+        payload,
+    })
+}
+
+fn connection_type() -> impl Strategy<Value = String> {
+    prop_oneof![
+        Just(ConnectionType::Standard),
+        Just(ConnectionType::Websocket),
+        Just(ConnectionType::Leafnode),
+        Just(ConnectionType::LeafnodeWs),
+        Just(ConnectionType::Mqtt),
+        Just(ConnectionType::MqttWs),
+        Just(ConnectionType::InProcess),
+    ]
+    .prop_map(|kind| kind.to_string())
+}
+
+fn user_permission_limits() -> impl Strategy<Value = UserPermissionLimits> {
+    (
+        permissions(),
+        option::of(option::of(nats_limits())),
+        option::of(any::<bool>()),
+        option::of(vec(connection_type(), 1..3)),
+    )
+        .prop_map(
+            |(permissions, nats_limits, bearer_token, allowed_connection_types)| {
+                UserPermissionLimits {
+                    permissions,
+                    limits: nats_limits.map(|nats_limits| Limits {
+                        user_limits: None,
+                        nats_limits,
+                    }),
+                    bearer_token,
+                    allowed_connection_types,
+                }
+            },
+        )
+}
+
+fn export_type() -> impl Strategy<Value = ExportType> {
+    prop_oneof![Just(ExportType::Stream), Just(ExportType::Service)]
+}
+
+fn import() -> impl Strategy<Value = Import> {
+    (
+        text(),
+        subject(),
+        public_key(KeyPairType::Account),
+        option::of(export_type()),
+        option::of(subject()),
+        option::of(any::<bool>()),
+        option::of(any::<bool>()),
+    )
+        .prop_map(
+            |(name, subject, account, export_type, local_subject, share, allow_trace)| Import {
+                name,
+                subject,
+                account,
+                export_type,
+                local_subject: local_subject.unwrap_or_default(),
+                share,
+                allow_trace,
+                ..Default::default()
+            },
+        )
+}
+
+fn export() -> impl Strategy<Value = Export> {
+    (
+        text(),
+        subject(),
+        option::of(export_type()),
+        option::of(any::<bool>()),
+        option::of(prop_oneof![
+            Just(ResponseType::Singleton),
+            Just(ResponseType::Stream),
+            Just(ResponseType::Chunked),
+        ]),
+        option::of(duration()),
+        option::of(any::<bool>()),
+        option::of(any::<bool>()),
+    )
+        .prop_map(
+            |(
+                name,
+                subject,
+                export_type,
+                token_req,
+                response_type,
+                response_threshold,
+                advertise,
+                allow_trace,
+            )| Export {
+                name,
+                subject,
+                export_type,
+                token_req,
+                response_type,
+                response_threshold,
+                advertise,
+                allow_trace,
+                ..Default::default()
+            },
+        )
+}
+
+fn account_limits() -> impl Strategy<Value = AccountLimits> {
+    (
+        limit(),
+        limit(),
+        option::of(any::<bool>()),
+        option::of(any::<bool>()),
+        limit(),
+        limit(),
+    )
+        .prop_map(
+            |(imports, exports, wildcard_exports, disallow_bearer, conn, leaf)| AccountLimits {
+                imports,
+                exports,
+                wildcard_exports,
+                disallow_bearer,
+                conn,
+                leaf,
+            },
+        )
+}
+
+fn jetstream_limits() -> impl Strategy<Value = JetStreamLimits> {
+    (
+        limit(),
+        limit(),
+        limit(),
+        limit(),
+        option::of(any::<bool>()),
+    )
+        .prop_map(
+            |(memory_storage, disk_storage, streams, consumer, max_bytes_required)| {
+                JetStreamLimits {
+                    memory_storage,
+                    disk_storage,
+                    streams,
+                    consumer,
+                    max_bytes_required,
+                    ..Default::default()
+                }
+            },
+        )
+}
+
+fn operator_limits() -> impl Strategy<Value = OperatorLimits> {
+    (
+        option::of(nats_limits()),
+        option::of(account_limits()),
+        option::of(jetstream_limits()),
+        option::of(btree_map("R[1-5]", jetstream_limits(), 1..3)),
+    )
+        .prop_map(|(nats, account, jetstream, tiered_limits)| OperatorLimits {
+            nats,
+            account,
+            jetstream,
+            tiered_limits,
+        })
+}
+
+fn signing_key() -> impl Strategy<Value = SigningKey> {
+    (
+        public_key(KeyPairType::Account),
+        option::of((
+            option::of("[a-z]{1,8}"),
+            option::of(user_permission_limits()),
+            option::of(text()),
+        )),
+    )
+        .prop_map(|(key, scope)| SigningKey {
+            scope: scope.map(|(role, template, description)| UserScope {
+                kind: ScopeType::UserScope,
+                key: key.clone(),
+                role,
+                template,
+                description,
+            }),
+            key,
+        })
+}
+
+impl Arbitrary for User {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (
+            user_permission_limits(),
+            option::of(public_key(KeyPairType::Account)),
+            tags(),
+        )
+            .prop_map(|(permissions, issuer_account, tags)| {
+                let mut user = User {
+                    permissions,
+                    issuer_account,
+                    ..Default::default()
+                };
+                user.generic_fields.tags = tags;
+                user
+            })
+            .boxed()
+    }
+}
+
+impl Arbitrary for Account {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (
+            option::of(vec(import(), 1..4)),
+            option::of(vec(export(), 1..4)),
+            option::of(operator_limits()),
+            option::of(vec(signing_key(), 1..3)),
+            option::of(permissions()),
+            tags(),
+        )
+            .prop_map(
+                |(imports, exports, limits, signing_keys, default_permissions, tags)| {
+                    let mut account = Account {
+                        imports,
+                        exports,
+                        limits,
+                        signing_keys: signing_keys.map(IndexSet::from_iter),
+                        default_permissions,
+                        ..Default::default()
+                    };
+                    account.generic_fields.tags = tags;
+                    account
+                },
+            )
+            .boxed()
+    }
+}
+
+impl Arbitrary for Operator {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (
+            option::of(vec(public_key(KeyPairType::Operator), 1..3)),
+            option::of("nats://[a-z]{1,12}:[0-9]{4}"),
+            option::of(vec("nats://[a-z]{1,12}:[0-9]{4}", 1..3)),
+            option::of(public_key(KeyPairType::Account)),
+            option::of("[0-9]\\.[0-9]{1,2}\\.[0-9]{1,2}"),
+            option::of(any::<bool>()),
+            tags(),
+        )
+            .prop_map(
+                |(
+                    signing_keys,
+                    account_server_url,
+                    operator_service_urls,
+                    system_account,
+                    assert_server_version,
+                    strict_signing_key_usage,
+                    tags,
+                )| {
+                    let mut operator = Operator {
+                        signing_keys,
+                        account_server_url,
+                        operator_service_urls,
+                        system_account,
+                        assert_server_version,
+                        strict_signing_key_usage,
+                        ..Default::default()
+                    };
+                    operator.generic_fields.tags = tags;
+                    operator
+                },
+            )
+            .boxed()
+    }
+}
+
+/// Claims are generated through [`ClaimsBuilder`], with a subject of the nkey type that the
+/// payload's claim type expects.
+impl<T> Arbitrary for Claims<T>
+where
+    T: Claim + Arbitrary + 'static,
+{
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        let subject_type = crate::expected_key_types(&T::CLAIM_TYPE)
+            .0
+            .first()
+            .cloned()
+            .unwrap_or(KeyPairType::Account);
+        (
+            any::<T>(),
+            option::of(text()),
+            public_key(subject_type),
+            option::of("[a-zA-Z0-9-]{1,16}"),
+            option::of(1u64..100_000_000),
+        )
+            .prop_map(|(payload, name, subject, audience, ttl)| {
+                let mut builder = ClaimsBuilder::new(payload).subject(subject);
+                if let Some(name) = name {
+                    builder = builder.name(name);
+                }
+                if let Some(audience) = audience {
+                    builder = builder.audience(audience);
+                }
+                if let Some(ttl) = ttl {
+                    builder = builder.expires_in(Duration::from_secs(ttl));
+                }
+                builder.build()
+            })
+            .boxed()
+    }
+}
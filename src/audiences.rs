@@ -0,0 +1,7 @@
+//! Well-known `aud` values used by nats-server.
+//!
+//! Authorization responses have no fixed audience: nats-server expects the id of the server
+//! that sent the request, see [`ServerID`](crate::authorization::ServerID).
+
+/// The `aud` nats-server sets on authorization requests sent to an auth callout service.
+pub const REQUEST: &str = "nats-authorization-request";
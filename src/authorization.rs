@@ -1,8 +1,17 @@
-use crate::{types::GenericFields, Claim, ClaimType, Claims};
+use crate::{
+    audiences,
+    types::GenericFields,
+    validation::{is_valid_public_key, ValidationResults},
+    Claim, ClaimType, Claims,
+};
+use anyhow::Context;
+use nkeys::{KeyPair, KeyPairType, XKey};
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeSet;
+use std::fmt;
 
 #[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct ClientInfo {
     pub host: String,
     pub id: u64,
@@ -17,7 +26,32 @@ pub struct ClientInfo {
     pub nonce: String,
 }
 
-#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+impl ClientInfo {
+    /// The `kind` nats-server reports for leafnode connections. Regular clients report `Client`.
+    pub const KIND_LEAFNODE: &'static str = "Leafnode";
+    /// The `type` nats-server reports for clients connecting over MQTT. Plain NATS clients
+    /// report `nats`.
+    pub const TYPE_MQTT: &'static str = "mqtt";
+    /// The `type` nats-server reports for clients connecting over websockets.
+    pub const TYPE_WEBSOCKET: &'static str = "websocket";
+
+    pub fn is_mqtt(&self) -> bool {
+        self.mqtt.as_deref().is_some_and(|id| !id.is_empty())
+            || self.client_type.eq_ignore_ascii_case(Self::TYPE_MQTT)
+    }
+
+    pub fn is_leafnode(&self) -> bool {
+        self.kind.eq_ignore_ascii_case(Self::KIND_LEAFNODE)
+    }
+
+    /// Websocket clients are reported with a `Client` kind, so this checks the client `type`.
+    pub fn is_websocket(&self) -> bool {
+        self.client_type.eq_ignore_ascii_case(Self::TYPE_WEBSOCKET)
+    }
+}
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct ConnectOpts {
     pub jwt: Option<String>,
     pub nkey: Option<String>,
@@ -28,18 +62,207 @@ pub struct ConnectOpts {
     pub name: Option<String>,
     pub lang: Option<String>,
     pub version: Option<String>,
-    pub protocol: u32,
+    pub protocol: Protocol,
+}
+
+/// Number of leading characters of the JWT kept in [`ConnectOpts`] debug output.
+const DEBUG_JWT_PREFIX_LEN: usize = 16;
+
+// Credentials are redacted so that connect options can be logged safely.
+impl fmt::Debug for ConnectOpts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let redacted = |value: &Option<String>| value.as_ref().map(|_| "[redacted]");
+        let jwt = self
+            .jwt
+            .as_ref()
+            .map(|jwt| match jwt.get(..DEBUG_JWT_PREFIX_LEN) {
+                Some(prefix) => format!("{prefix}..."),
+                None => "[redacted]".to_string(),
+            });
+
+        f.debug_struct("ConnectOpts")
+            .field("jwt", &jwt)
+            .field("nkey", &self.nkey)
+            .field("sig", &redacted(&self.sig))
+            .field("auth_token", &redacted(&self.auth_token))
+            .field("user", &self.user)
+            .field("pass", &redacted(&self.pass))
+            .field("name", &self.name)
+            .field("lang", &self.lang)
+            .field("version", &self.version)
+            .field("protocol", &self.protocol)
+            .finish()
+    }
+}
+
+impl ConnectOpts {
+    /// Returns `true` if the client uses the dynamic protocol, supporting async INFO updates.
+    pub fn is_dynamic(&self) -> bool {
+        self.protocol == Protocol::Dynamic
+    }
+
+    /// Classifies the credentials in the request by which fields are set. Empty strings count
+    /// as unset.
+    pub fn auth_mode(&self) -> AuthMode {
+        let set = |value: &Option<String>| value.as_deref().is_some_and(|v| !v.is_empty());
+        let modes = [
+            (set(&self.jwt), AuthMode::Jwt),
+            (!set(&self.jwt) && set(&self.nkey), AuthMode::Nkey),
+            (set(&self.auth_token), AuthMode::Bearer),
+            (set(&self.user) || set(&self.pass), AuthMode::UserPass),
+        ];
+        let mut present = modes.into_iter().filter(|(set, _)| *set);
+        match (present.next(), present.next()) {
+            (None, _) => AuthMode::None,
+            (Some((_, mode)), None) => mode,
+            (Some(_), Some(_)) => AuthMode::Ambiguous,
+        }
+    }
+}
+
+/// The kind of credentials a client presented in CONNECT, see [`ConnectOpts::auth_mode`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthMode {
+    /// A user JWT, normally with a `sig` over the server nonce.
+    Jwt,
+    /// A bare nkey with a `sig`, without a JWT.
+    Nkey,
+    /// An `auth_token`.
+    Bearer,
+    /// A username and password.
+    UserPass,
+    /// No credentials at all.
+    None,
+    /// More than one kind of credentials, which auth services should reject.
+    Ambiguous,
+}
+
+/// The client protocol version sent in CONNECT.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(from = "u32", into = "u32")]
+pub enum Protocol {
+    /// The original protocol, without async INFO updates.
+    #[default]
+    Original,
+    /// The dynamic protocol, where the server may send async INFO updates.
+    Dynamic,
+    /// A protocol version this crate does not know about.
+    Unknown(u32),
+}
+
+impl From<u32> for Protocol {
+    fn from(protocol: u32) -> Self {
+        match protocol {
+            0 => Protocol::Original,
+            1 => Protocol::Dynamic,
+            other => Protocol::Unknown(other),
+        }
+    }
+}
+
+impl From<Protocol> for u32 {
+    fn from(protocol: Protocol) -> Self {
+        match protocol {
+            Protocol::Original => 0,
+            Protocol::Dynamic => 1,
+            Protocol::Unknown(other) => other,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct ClientTLS {
-    version: String,
-    cipher: String,
-    certs: Vec<String>,
-    verified_chains: Vec<Vec<String>>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub version: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub cipher: String,
+    /// PEM encoded certificates presented by the client, leaf first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub certs: Vec<String>,
+    /// PEM encoded chains the server verified the client certificate against.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub verified_chains: Vec<Vec<String>>,
+}
+
+impl ClientTLS {
+    /// Returns the client's own certificate, if it presented one.
+    pub fn leaf_certificate(&self) -> Option<&str> {
+        self.certs.first().map(String::as_str)
+    }
+
+    /// Returns `true` if the server verified the client certificate against a trusted chain.
+    pub fn verified(&self) -> bool {
+        !self.verified_chains.is_empty()
+    }
+
+    /// Parses the client's leaf certificate, given either as PEM or as base64 encoded DER.
+    #[cfg(feature = "x509")]
+    pub fn parse_leaf(&self) -> anyhow::Result<ParsedCert> {
+        use base64::{engine::general_purpose::STANDARD, Engine as _};
+        use x509_parser::{extensions::GeneralName, prelude::*};
+
+        let leaf = self
+            .leaf_certificate()
+            .ok_or_else(|| anyhow::anyhow!("client did not present a certificate"))?;
+        let der = if leaf.trim_start().starts_with("-----BEGIN") {
+            let (_, pem) = x509_parser::pem::parse_x509_pem(leaf.as_bytes())
+                .map_err(|e| anyhow::anyhow!("invalid PEM certificate: {e}"))?;
+            pem.contents
+        } else {
+            STANDARD.decode(leaf.trim())?
+        };
+        let (_, cert) = X509Certificate::from_der(&der)
+            .map_err(|e| anyhow::anyhow!("invalid certificate: {e}"))?;
+
+        let sans = cert
+            .subject_alternative_name()
+            .map_err(|e| anyhow::anyhow!("invalid subject alternative names: {e}"))?
+            .map(|ext| {
+                ext.value
+                    .general_names
+                    .iter()
+                    .filter_map(|name| match name {
+                        GeneralName::DNSName(s)
+                        | GeneralName::RFC822Name(s)
+                        | GeneralName::URI(s) => Some(s.to_string()),
+                        GeneralName::IPAddress(ip) => match ip.len() {
+                            4 => <[u8; 4]>::try_from(*ip)
+                                .ok()
+                                .map(|ip| std::net::Ipv4Addr::from(ip).to_string()),
+                            16 => <[u8; 16]>::try_from(*ip)
+                                .ok()
+                                .map(|ip| std::net::Ipv6Addr::from(ip).to_string()),
+                            _ => None,
+                        },
+                        _ => None,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(ParsedCert {
+            subject: cert.subject().to_string(),
+            issuer: cert.issuer().to_string(),
+            sans,
+        })
+    }
+}
+
+/// The identifying fields of a parsed client certificate.
+#[cfg(feature = "x509")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedCert {
+    /// The subject distinguished name, such as `CN=alice, O=wasmCloud`.
+    pub subject: String,
+    pub issuer: String,
+    /// DNS names, email addresses, URIs and IP addresses from the subject alternative names.
+    pub sans: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub struct AuthRequest {
     #[serde(rename = "server_id")]
@@ -57,6 +280,14 @@ pub struct AuthRequest {
 }
 
 impl AuthRequest {
+    /// The `aud` of every authorization request, see [`audiences::REQUEST`].
+    pub const EXPECTED_AUDIENCE: &'static str = audiences::REQUEST;
+
+    /// Returns the tags the connecting client presented, if any.
+    pub fn client_tags(&self) -> &[String] {
+        self.client_info.tags.as_deref().unwrap_or_default()
+    }
+
     fn new() -> Self {
         Self {
             server: ServerID::default(),
@@ -65,7 +296,10 @@ impl AuthRequest {
             connect_opts: ConnectOpts::default(),
             client_tls: None,
             request_nonce: None,
-            generic_fields: GenericFields::default(),
+            generic_fields: GenericFields {
+                claim_type: Self::CLAIM_TYPE,
+                ..Default::default()
+            },
         }
     }
 }
@@ -77,10 +311,26 @@ impl Default for AuthRequest {
 }
 
 impl Claim for AuthRequest {
-    fn validate() {}
+    const CLAIM_TYPE: ClaimType = ClaimType::AuthorizationRequest;
+
+    /// Requires the `aud` nats-server sets on every request, [`audiences::REQUEST`].
+    fn validate(claims: &Claims<Self>, vr: &mut ValidationResults) {
+        if claims.aud.as_deref() != Some(AuthRequest::EXPECTED_AUDIENCE) {
+            vr.add_error(format!(
+                "authorization request audience must be {}, found {}",
+                AuthRequest::EXPECTED_AUDIENCE,
+                claims.aud.as_deref().unwrap_or("none")
+            ));
+        }
+    }
+
+    fn generic_fields(&self) -> Option<&GenericFields> {
+        Some(&self.generic_fields)
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct ServerID {
     pub name: String,
     pub host: String,
@@ -96,10 +346,26 @@ pub struct ServerID {
     pub xkey: Option<String>,
 }
 
+impl ServerID {
+    /// Returns `true` if the server advertised an xkey, so auth responses may be encrypted.
+    pub fn supports_encryption(&self) -> bool {
+        self.xkey.as_deref().is_some_and(|xkey| !xkey.is_empty())
+    }
+
+    /// Returns the name of the cluster the server belongs to, if any.
+    pub fn in_cluster(&self) -> Option<&str> {
+        self.cluster
+            .as_deref()
+            .filter(|cluster| !cluster.is_empty())
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct AuthResponse {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub jwt: String,
-    #[serde(skip_serializing_if = "String::is_empty")]
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub error: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub issuer_account: Option<String>,
@@ -114,7 +380,7 @@ impl AuthResponse {
             error: String::new(),
             issuer_account: None,
             generic_fields: GenericFields {
-                claim_type: ClaimType::AuthorizationResponse,
+                claim_type: Self::CLAIM_TYPE,
                 version: 2,
                 ..Default::default()
             },
@@ -124,6 +390,345 @@ impl AuthResponse {
     }
 }
 
+impl Claims<AuthResponse> {
+    /// Sets the audience of the response to the public key of the server that sent the
+    /// request, the `id` of its [`ServerID`]. nats-server only accepts responses addressed to it.
+    pub fn set_audience(&mut self, aud: impl Into<String>) {
+        self.aud = Some(aud.into());
+    }
+
+    /// Returns the response addressed to the server `aud`, see [`Claims::set_audience`].
+    pub fn with_audience(mut self, aud: impl Into<String>) -> Self {
+        self.set_audience(aud);
+        self
+    }
+
+    /// Signs the response with `key_pair`, then seals the token from `responder_xkey` for the
+    /// server that advertised `server_xkey_public` in its [`ServerID`].
+    pub fn encode_encrypted(
+        &self,
+        key_pair: &KeyPair,
+        responder_xkey: &XKey,
+        server_xkey_public: &str,
+    ) -> anyhow::Result<Vec<u8>> {
+        let server_xkey = XKey::from_public_key(server_xkey_public)
+            .with_context(|| format!("invalid server xkey {server_xkey_public}"))?;
+        let token = self.encode(key_pair)?;
+        Ok(responder_xkey.seal(token.as_bytes(), &server_xkey)?)
+    }
+}
+
+/// Opens an auth response sealed with [`Claims::encode_encrypted`] by the responder owning
+/// `responder_xkey_public`, then decodes and verifies the token inside.
+pub fn decode_encrypted_auth_response(
+    payload: &[u8],
+    server_xkey: &XKey,
+    responder_xkey_public: &str,
+) -> anyhow::Result<Claims<AuthResponse>> {
+    let responder_xkey = XKey::from_public_key(responder_xkey_public)
+        .with_context(|| format!("invalid responder xkey {responder_xkey_public}"))?;
+    let token = server_xkey
+        .open(payload, &responder_xkey)
+        .context("failed to open encrypted auth response")?;
+    let token = String::from_utf8(token).context("encrypted auth response is not a token")?;
+    Ok(Claims::decode(&token)?)
+}
+
 impl Claim for AuthResponse {
-    fn validate() {}
+    const CLAIM_TYPE: ClaimType = ClaimType::AuthorizationResponse;
+
+    fn validate(claims: &Claims<Self>, vr: &mut ValidationResults) {
+        // like nats.go, require the response to be addressed to the requesting server
+        let aud = claims.aud.as_deref().unwrap_or_default();
+        if !is_valid_public_key(aud, KeyPairType::Server) {
+            vr.add_error(format!(
+                "authorization response audience must be a server public key, found {}",
+                if aud.is_empty() { "none" } else { aud }
+            ));
+        }
+    }
+
+    fn generic_fields(&self) -> Option<&GenericFields> {
+        Some(&self.generic_fields)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_connect_opts_debug_redacts_credentials() {
+        let opts = ConnectOpts {
+            jwt: Some("eyJ0eXAiOiJKV1QiLCJhbGciOiJlZDI1NTE5LW5rZXkifQ.payload.sig".to_string()),
+            auth_token: Some("s3cr3t-token".to_string()),
+            pass: Some("hunter2".to_string()),
+            user: Some("alice".to_string()),
+            ..Default::default()
+        };
+        let debug = format!("{opts:?}");
+
+        assert!(debug.contains("auth_token: Some(\"[redacted]\")"));
+        assert!(!debug.contains("s3cr3t-token"));
+        assert!(!debug.contains("hunter2"));
+        assert!(!debug.contains("payload"));
+        assert!(debug.contains("alice"));
+    }
+
+    #[test]
+    fn test_auth_response_audience() {
+        let account = KeyPair::new_account();
+        let response = AuthResponse::generic_claim(KeyPair::new_user().public_key());
+        let mut vr = ValidationResults::new();
+        response.validate(&mut vr);
+        assert!(vr.is_blocking(false));
+
+        let mut vr = ValidationResults::new();
+        response
+            .clone()
+            .with_audience("responder-east")
+            .validate(&mut vr);
+        assert!(vr.is_blocking(false));
+
+        let server = KeyPair::new_server().public_key();
+        let response = response.with_audience(&server);
+        let token = response.encode(&account).unwrap();
+        let decoded = Claims::<AuthResponse>::decode(&token).unwrap();
+        assert_eq!(decoded.aud, Some(server));
+        let mut vr = ValidationResults::new();
+        decoded.validate(&mut vr);
+        assert!(vr.is_empty());
+    }
+
+    fn auth_request(aud: Option<&str>) -> Claims<AuthRequest> {
+        let mut claims = Claims::new(AuthRequest::default());
+        claims.aud = aud.map(str::to_string);
+        claims
+    }
+
+    #[test]
+    fn test_server_id_capabilities() {
+        let mut server: ServerID = serde_json::from_str(
+            r#"{
+                "host": "0.0.0.0",
+                "id": "NCLH2BAHSW2ASMRX7IIVUPQRUDTC556SMEY5L7PWNHZUJYQ7UDV7C7BA",
+                "name": "NCLH2BAHSW2ASMRX7IIVUPQRUDTC556SMEY5L7PWNHZUJYQ7UDV7C7BA",
+                "version": "2.10.18",
+                "xkey": "XAVESR4X4YVIJJ7VHJWAIQYRU7TMIZCYD36HYSYBYWJWB5GKHDFHETUU"
+            }"#,
+        )
+        .unwrap();
+        assert!(server.supports_encryption());
+        assert_eq!(server.in_cluster(), None);
+
+        server.xkey = Some(String::new());
+        server.cluster = Some("east".to_string());
+        assert!(!server.supports_encryption());
+        assert_eq!(server.in_cluster(), Some("east"));
+    }
+
+    #[test]
+    fn test_auth_request_audience() {
+        let mut vr = ValidationResults::new();
+        auth_request(Some(AuthRequest::EXPECTED_AUDIENCE)).validate(&mut vr);
+        assert!(!vr.is_blocking(false));
+    }
+
+    #[test]
+    fn test_auth_request_wrong_audience() {
+        let mut vr = ValidationResults::new();
+        auth_request(Some("nats-authorization-response")).validate(&mut vr);
+        assert!(vr.is_blocking(false));
+
+        let mut vr = ValidationResults::new();
+        auth_request(None).validate(&mut vr);
+        assert!(vr.is_blocking(false));
+    }
+
+    #[test]
+    fn test_client_info_kind() {
+        let nats: ClientInfo = serde_json::from_str(
+            r#"{
+                "host": "127.0.0.1",
+                "id": 21,
+                "kind": "Client",
+                "name": "NATS CLI Version development",
+                "name_tag": "wasmCloud User Auth-registration",
+                "nonce": "6KZMq4gzqULs8Cw",
+                "type": "nats",
+                "user": "UCB7G4JWCLUIJE7552IRU3EUCPYHSDGIEBANNQ2DLPS4GHKNFOQZORUA"
+            }"#,
+        )
+        .unwrap();
+        assert!(!nats.is_mqtt());
+        assert!(!nats.is_leafnode());
+        assert!(!nats.is_websocket());
+
+        let mqtt = ClientInfo {
+            kind: "Client".to_string(),
+            client_type: "mqtt".to_string(),
+            mqtt: Some("sensor-1".to_string()),
+            ..Default::default()
+        };
+        assert!(mqtt.is_mqtt());
+        assert!(!mqtt.is_leafnode());
+
+        let leafnode = ClientInfo {
+            kind: "Leafnode".to_string(),
+            ..Default::default()
+        };
+        assert!(leafnode.is_leafnode());
+        assert!(!leafnode.is_mqtt());
+
+        let websocket = ClientInfo {
+            kind: "Client".to_string(),
+            client_type: "websocket".to_string(),
+            ..Default::default()
+        };
+        assert!(websocket.is_websocket());
+    }
+
+    #[test]
+    fn test_encrypted_auth_response_round_trip() {
+        let account = KeyPair::new_account();
+        let server_xkey = XKey::new();
+        let responder_xkey = XKey::new();
+        let response = AuthResponse::generic_claim(KeyPair::new_user().public_key())
+            .with_audience("NDIIXUGVUTBDQPLXXUXPQ6BSNPDHBFMPXZSJHHWSRZDWJAU5GEDTB6GA");
+
+        let sealed = response
+            .encode_encrypted(&account, &responder_xkey, &server_xkey.public_key())
+            .unwrap();
+        let decoded =
+            decode_encrypted_auth_response(&sealed, &server_xkey, &responder_xkey.public_key())
+                .unwrap();
+        assert_eq!(decoded.sub, response.sub);
+        assert_eq!(decoded.aud, response.aud);
+        assert_eq!(decoded.iss, account.public_key());
+
+        assert!(decode_encrypted_auth_response(
+            &sealed,
+            &XKey::new(),
+            &responder_xkey.public_key()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_connect_opts_auth_mode() {
+        let opts = ConnectOpts {
+            jwt: Some("eyJ0eXAiOiJKV1QiLCJhbGciOiJlZDI1NTE5LW5rZXkifQ.payload.sig".to_string()),
+            sig: Some("c2ln".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(opts.auth_mode(), AuthMode::Jwt);
+
+        let opts = ConnectOpts {
+            auth_token: Some("s3cr3t-token".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(opts.auth_mode(), AuthMode::Bearer);
+
+        let opts = ConnectOpts {
+            jwt: Some("eyJ0eXAiOiJKV1QiLCJhbGciOiJlZDI1NTE5LW5rZXkifQ.payload.sig".to_string()),
+            user: Some("alice".to_string()),
+            pass: Some("hunter2".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(opts.auth_mode(), AuthMode::Ambiguous);
+
+        let opts = ConnectOpts {
+            nkey: Some(KeyPair::new_user().public_key()),
+            user: Some(String::new()),
+            ..Default::default()
+        };
+        assert_eq!(opts.auth_mode(), AuthMode::Nkey);
+        assert_eq!(ConnectOpts::default().auth_mode(), AuthMode::None);
+    }
+
+    #[test]
+    fn test_connect_opts_protocol() {
+        let opts: ConnectOpts = serde_json::from_str(r#"{"protocol": 0}"#).unwrap();
+        assert_eq!(opts.protocol, Protocol::Original);
+        assert!(!opts.is_dynamic());
+
+        let opts: ConnectOpts = serde_json::from_str(r#"{"protocol": 1}"#).unwrap();
+        assert_eq!(opts.protocol, Protocol::Dynamic);
+        assert!(opts.is_dynamic());
+
+        let opts: ConnectOpts = serde_json::from_str(r#"{"protocol": 7}"#).unwrap();
+        assert_eq!(opts.protocol, Protocol::Unknown(7));
+        assert_eq!(serde_json::to_value(&opts).unwrap()["protocol"], 7);
+    }
+
+    #[test]
+    fn test_auth_response_set_audience() {
+        let mut claims = AuthResponse::generic_claim("UABC".to_string());
+        claims.set_audience("NCLH2BAHSW2ASMRX7IIVUPQRUDTC556SMEY5L7PWNHZUJYQ7UDV7C7BA");
+        assert_eq!(
+            claims.aud.as_deref(),
+            Some("NCLH2BAHSW2ASMRX7IIVUPQRUDTC556SMEY5L7PWNHZUJYQ7UDV7C7BA")
+        );
+    }
+
+    #[test]
+    fn test_client_tls() {
+        let tls: ClientTLS = serde_json::from_value(serde_json::json!({
+            "version": "1.3",
+            "cipher": "TLS_AES_128_GCM_SHA256",
+            "certs": ["-----BEGIN CERTIFICATE-----\nleaf", "-----BEGIN CERTIFICATE-----\nca"],
+            "verified_chains": [["-----BEGIN CERTIFICATE-----\nleaf", "-----BEGIN CERTIFICATE-----\nca"]],
+        }))
+        .unwrap();
+        assert_eq!(
+            tls.leaf_certificate(),
+            Some("-----BEGIN CERTIFICATE-----\nleaf")
+        );
+        assert!(tls.verified());
+
+        let tls: ClientTLS = serde_json::from_str(r#"{"version":"1.3"}"#).unwrap();
+        assert_eq!(tls.leaf_certificate(), None);
+        assert!(!tls.verified());
+    }
+
+    #[cfg(feature = "x509")]
+    const TEST_CERT: &str = "\
+-----BEGIN CERTIFICATE-----\n\
+MIIB0TCCAXigAwIBAgIUQj9kqnWL98+7JymBfGMDoBCag4swCgYIKoZIzj0EAwIw\n\
+JDEOMAwGA1UEAwwFYWxpY2UxEjAQBgNVBAoMCXdhc21DbG91ZDAgFw0yNjEwMTYx\n\
+MzUwNThaGA8yMTI2MDkyMjEzNTA1OFowJDEOMAwGA1UEAwwFYWxpY2UxEjAQBgNV\n\
+BAoMCXdhc21DbG91ZDBZMBMGByqGSM49AgEGCCqGSM49AwEHA0IABE6GnYvpaS06\n\
+YSnCb9mXI2jRNIuprz/4X1mf3UhXjDhmcSD6+o82OMj49N/k57hfl+WqsUBdueMA\n\
+WA3avvL6sESjgYUwgYIwHQYDVR0OBBYEFODOGHqwEl2JfneCYEX4fhHX/BBRMB8G\n\
+A1UdIwQYMBaAFODOGHqwEl2JfneCYEX4fhHX/BBRMA8GA1UdEwEB/wQFMAMBAf8w\n\
+LwYDVR0RBCgwJoIRYWxpY2UuZXhhbXBsZS5jb22BEWFsaWNlQGV4YW1wbGUuY29t\n\
+MAoGCCqGSM49BAMCA0cAMEQCICJVTaoIJbb6262O0R7jR7XQFRpaxwCqBrzlXYvP\n\
+Uk3UAiBBituDn2X8V0CB6O/2gj7YImSBeTnxrNhApjLxevjrTQ==\n\
+-----END CERTIFICATE-----\n\
+";
+
+    #[cfg(feature = "x509")]
+    #[test]
+    fn test_parse_leaf() {
+        let tls = ClientTLS {
+            certs: vec![TEST_CERT.to_string()],
+            ..Default::default()
+        };
+        let cert = tls.parse_leaf().unwrap();
+        assert_eq!(cert.subject, "CN=alice, O=wasmCloud");
+        assert_eq!(cert.issuer, "CN=alice, O=wasmCloud");
+        assert_eq!(cert.sans, vec!["alice.example.com", "alice@example.com"]);
+
+        let der = TEST_CERT
+            .lines()
+            .filter(|l| !l.starts_with("-----"))
+            .collect::<String>();
+        let tls = ClientTLS {
+            certs: vec![der],
+            ..Default::default()
+        };
+        assert_eq!(tls.parse_leaf().unwrap(), cert);
+
+        assert!(ClientTLS::default().parse_leaf().is_err());
+    }
 }
@@ -0,0 +1,113 @@
+use crate::{
+    account::Account, operator::Operator, user::User, validation::is_valid_public_key, Claims,
+    JwtError,
+};
+use anyhow::Result;
+use nkeys::{KeyPair, KeyPairType};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use std::result::Result as StdResult;
+
+/// The decoded claims of an operator → account → user trust chain that passed
+/// [`verify_chain`].
+#[derive(Debug, Clone)]
+pub struct VerifiedChain {
+    pub operator: Claims<Operator>,
+    pub account: Claims<Account>,
+    pub user: Claims<User>,
+}
+
+/// Verifies a complete trust chain in one call.
+///
+/// Each token's signature and claim type are checked, and its subject must be a public key of
+/// the matching kind. The account must be issued by the operator or one of its
+/// signing keys, and the user must be issued by the account or one of its signing keys, with
+/// `issuer_account` naming the account when a signing key was used. When the operator sets
+/// `strict_signing_key_usage`, the account must be issued by a signing key. None of the tokens
+/// may be expired.
+pub fn verify_chain(
+    operator_jwt: &str,
+    account_jwt: &str,
+    user_jwt: &str,
+) -> Result<VerifiedChain> {
+    let operator = Claims::<Operator>::decode_checked(operator_jwt)?;
+    let account = Claims::<Account>::decode_checked(account_jwt)?;
+    let user = Claims::<User>::decode_checked(user_jwt)?;
+
+    for (kind, subject, key_type) in [
+        ("operator", &operator.sub, KeyPairType::Operator),
+        ("account", &account.sub, KeyPairType::Account),
+        ("user", &user.sub, KeyPairType::User),
+    ] {
+        if !is_valid_public_key(subject, key_type) {
+            return Err(anyhow::anyhow!(
+                "{kind} token subject {subject} is not a public {kind} key"
+            ));
+        }
+    }
+
+    for (kind, expired) in [
+        ("operator", operator.is_expired()),
+        ("account", account.is_expired()),
+        ("user", user.is_expired()),
+    ] {
+        if expired {
+            return Err(anyhow::anyhow!("{kind} token has expired"));
+        }
+    }
+
+    if !operator.nats.validates_account(&operator.sub, &account) {
+        return Err(anyhow::anyhow!(
+            "account {} was not issued by operator {}",
+            account.sub,
+            operator.sub
+        ));
+    }
+    if operator.nats.strict_signing_key_usage == Some(true) && account.iss == operator.sub {
+        return Err(anyhow::anyhow!(
+            "operator {} requires strict signing key usage, but account {} is signed by its identity key",
+            operator.sub,
+            account.sub
+        ));
+    }
+
+    let issuer_account = user.nats.issuer_account.as_deref().unwrap_or(&user.iss);
+    if issuer_account != account.sub {
+        return Err(anyhow::anyhow!(
+            "user {} belongs to account {issuer_account}, not {}",
+            user.sub,
+            account.sub
+        ));
+    }
+    if user.iss != account.sub && !account.nats.has_signing_key(&user.iss) {
+        return Err(anyhow::anyhow!(
+            "user {} was not issued by account {} or one of its signing keys",
+            user.sub,
+            account.sub
+        ));
+    }
+
+    Ok(VerifiedChain {
+        operator,
+        account,
+        user,
+    })
+}
+
+/// Verifies a batch of account tokens that must all be issued by the `trusted` operator key,
+/// such as when an account resolver loads its store on startup.
+///
+/// Results are returned in the same order as `tokens`. With the `rayon` feature enabled the
+/// tokens are verified in parallel.
+pub fn verify_many(
+    tokens: &[&str],
+    trusted: &KeyPair,
+) -> Vec<StdResult<Claims<Account>, JwtError>> {
+    let trusted_public = trusted.public_key();
+    let verify = |token: &&str| Claims::<Account>::decode_trusted(token, trusted, &trusted_public);
+
+    #[cfg(feature = "rayon")]
+    return tokens.par_iter().map(verify).collect();
+    #[cfg(not(feature = "rayon"))]
+    return tokens.iter().map(verify).collect();
+}
@@ -1,26 +1,41 @@
 use std::{
+    borrow::Cow,
+    collections::BTreeMap,
     error::Error,
     fmt::{self, Display},
     result::Result as StdResult,
     str::FromStr,
-    time::UNIX_EPOCH,
+    time::{Duration, UNIX_EPOCH},
 };
 
-use anyhow::{Context, Result};
+use anyhow::Result;
 use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
 use data_encoding::BASE32_NOPAD;
-use nkeys::KeyPair;
+use nkeys::{KeyPair, KeyPairType};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use sha2::{Digest, Sha512_256};
+use types::GenericFields;
+use validation::{is_valid_public_key, ValidationResults};
 
 pub mod account;
 pub mod activation;
+#[cfg(feature = "proptest")]
+mod arbitrary;
+pub mod audiences;
 pub mod authorization;
+pub mod chain;
 pub mod operator;
 pub mod types;
 pub mod user;
 pub mod validation;
 
+/// The maximum length in bytes of a claim `name`.
+pub const MAX_NAME_LEN: usize = 255;
+
+/// The claims `nats.version` this crate understands. Version 1 tokens use an incompatible
+/// layout and are rejected on decode.
+pub const SUPPORTED_VERSION: u32 = 2;
+
 const HEADER_TYPE: &str = "JWT";
 const HEADER_ALGORITHM: &str = "ed25519-nkey";
 
@@ -31,23 +46,56 @@ pub struct ClaimsHeader {
 
     #[serde(rename = "alg")]
     algorithm: String,
+
+    /// Header fields this crate doesn't model, such as `kid`, kept for diagnostics.
+    #[serde(flatten)]
+    extra: BTreeMap<String, serde_json::Value>,
+}
+
+impl ClaimsHeader {
+    fn new() -> Self {
+        Self {
+            header_type: HEADER_TYPE.to_string(),
+            algorithm: HEADER_ALGORITHM.to_string(),
+            extra: BTreeMap::new(),
+        }
+    }
+
+    /// Parses and checks the header of `token` without looking at its payload or signature.
+    pub fn from_token(token: &str) -> StdResult<Self, JwtError> {
+        let token = normalize_token(token);
+        let [header, _, _] = split_token(&token)?;
+        Self::decode(header)
+    }
+
+    /// Decodes and checks a header segment, reporting base64 and JSON failures as such.
+    fn decode(header: &str) -> StdResult<Self, JwtError> {
+        Ok(decode_claims::<ClaimsHeader>(header)?.check()?)
+    }
+
+    fn check(self) -> StdResult<Self, ParseClaimsHeaderError> {
+        if self.header_type != HEADER_TYPE {
+            return Err(ParseClaimsHeaderError::UnsupportedType(self.header_type));
+        }
+        if self.algorithm != HEADER_ALGORITHM {
+            return Err(ParseClaimsHeaderError::UnsupportedAlgorithm(self.algorithm));
+        }
+        Ok(self)
+    }
+
+    /// Returns the header fields other than `typ` and `alg`. These are ignored when decoding.
+    pub fn headers_raw(&self) -> &BTreeMap<String, serde_json::Value> {
+        &self.extra
+    }
 }
 
 impl FromStr for ClaimsHeader {
     type Err = ParseClaimsHeaderError;
 
     fn from_str(header: &str) -> StdResult<Self, Self::Err> {
-        let header: ClaimsHeader =
-            decode_claims(header).map_err(|e| ParseClaimsHeaderError::Unknown(e.to_string()))?;
-        if header.header_type != HEADER_TYPE {
-            return Err(ParseClaimsHeaderError::UnsupportedType(header.header_type));
-        }
-        if header.algorithm != HEADER_ALGORITHM {
-            return Err(ParseClaimsHeaderError::UnsupportedAlgorithm(
-                header.algorithm,
-            ));
-        }
-        Ok(header)
+        decode_claims::<ClaimsHeader>(header)
+            .map_err(|e| ParseClaimsHeaderError::Unknown(e.to_string()))?
+            .check()
     }
 }
 
@@ -75,7 +123,50 @@ impl fmt::Display for ParseClaimsHeaderError {
 
 impl Error for ParseClaimsHeaderError {}
 
-#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+/// Errors returned when encoding or decoding tokens.
+#[derive(Debug, thiserror::Error)]
+pub enum JwtError {
+    #[error("malformed token")]
+    MalformedToken,
+    #[error("unsupported type: {0}")]
+    UnsupportedType(String),
+    #[error("unsupported algorithm: {0}")]
+    UnsupportedAlgorithm(String),
+    #[error("invalid issuer public key: {0}")]
+    InvalidIssuer(String),
+    #[error("signature verification failed")]
+    SignatureVerification,
+    #[error("failed to sign token: {0}")]
+    Signing(anyhow::Error),
+    #[error("unsupported claims version {0}, only version {SUPPORTED_VERSION} is supported")]
+    UnsupportedVersion(u32),
+    #[error("expected a {expected} claim, found {found}")]
+    ClaimTypeMismatch {
+        expected: ClaimType,
+        found: ClaimType,
+    },
+    #[error("unknown fields in claims: {}", .0.join(", "))]
+    UnknownFields(Vec<String>),
+    #[error("invalid base64: {0}")]
+    Base64(#[from] base64::DecodeError),
+    #[error("invalid json: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+impl From<ParseClaimsHeaderError> for JwtError {
+    fn from(err: ParseClaimsHeaderError) -> Self {
+        match err {
+            ParseClaimsHeaderError::UnsupportedAlgorithm(alg) => {
+                JwtError::UnsupportedAlgorithm(alg)
+            }
+            ParseClaimsHeaderError::UnsupportedType(typ) => JwtError::UnsupportedType(typ),
+            ParseClaimsHeaderError::Unknown(_) => JwtError::MalformedToken,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum ClaimType {
     Operator,
@@ -102,24 +193,35 @@ impl Display for ClaimType {
     }
 }
 
+/// The JWT claims envelope.
+///
+/// Fields are declared in the order nats.go serializes `ClaimsData` followed by `nats`, since
+/// the signature and `jti` cover the exact serialized bytes. Keep this order (and that of the
+/// payload structs) in sync with nats.go so tokens match those produced by nsc.
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Claims<T> {
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(
+        default,
+        deserialize_with = "deserialize_audience",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub aud: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub exp: Option<i64>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub jti: String,
     pub iat: u64,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub id: Option<String>,
     pub iss: String,
-    pub jti: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
-    pub nats: T,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub nbf: Option<i64>,
     #[serde(skip_serializing_if = "String::is_empty")]
     pub sub: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub nats: T,
 }
 
 impl<T> Claims<T>
@@ -140,12 +242,84 @@ where
             sub: String::new(),
         }
     }
+
+    /// Starts building claims around a default payload.
+    pub fn builder() -> ClaimsBuilder<T>
+    where
+        T: Default,
+    {
+        ClaimsBuilder::new(T::default())
+    }
+}
+
+/// Fluent construction of a [`Claims`] envelope and its payload.
+#[derive(Debug, Clone)]
+pub struct ClaimsBuilder<T> {
+    claims: Claims<T>,
+}
+
+impl<T: Claim> ClaimsBuilder<T> {
+    pub fn new(payload: T) -> Self {
+        Self {
+            claims: Claims::new(payload),
+        }
+    }
+
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.claims.name = Some(name.into());
+        self
+    }
+
+    pub fn subject(mut self, subject: impl Into<String>) -> Self {
+        self.claims.sub = subject.into();
+        self
+    }
+
+    pub fn audience(mut self, audience: impl Into<String>) -> Self {
+        self.claims.aud = Some(audience.into());
+        self
+    }
+
+    /// Expires the claims `ttl` from now.
+    pub fn expires_in(mut self, ttl: Duration) -> Self {
+        self.claims.exp = Some(unix_now() + ttl.as_secs() as i64);
+        self
+    }
+
+    /// Makes the claims valid only once `delay` has passed.
+    pub fn not_before_in(mut self, delay: Duration) -> Self {
+        self.claims.nbf = Some(unix_now() + delay.as_secs() as i64);
+        self
+    }
+
+    pub fn payload(mut self, payload: T) -> Self {
+        self.claims.nats = payload;
+        self
+    }
+
+    pub fn build(self) -> Claims<T> {
+        self.claims
+    }
 }
 
 impl<T> Claims<T>
 where
     T: Claim + DeserializeOwned + Serialize + Clone,
 {
+    /// Returns `true` if `exp` is set and lies in the past.
+    pub fn is_expired(&self) -> bool {
+        self.exp.is_some_and(|exp| exp < unix_now())
+    }
+
+    /// Returns how long until `exp`: `None` if unset, and zero once the claims have expired.
+    pub fn remaining_ttl(&self) -> Option<Duration> {
+        let exp = UNIX_EPOCH + Duration::from_secs(self.exp?.max(0) as u64);
+        Some(
+            exp.duration_since(std::time::SystemTime::now())
+                .unwrap_or(Duration::ZERO),
+        )
+    }
+
     pub fn payload(&self) -> &T {
         &self.nats
     }
@@ -154,41 +328,455 @@ where
         &mut self.nats
     }
 
-    pub fn decode(token: &str) -> Result<Claims<T>> {
-        let parts: Vec<&str> = token.split('.').collect();
-        if parts.len() != 3 {
-            return Err(anyhow::anyhow!("Invalid JWT"));
-        }
+    /// Decodes a token and verifies its signature against the issuer's public key.
+    ///
+    /// Verification is delegated to ed25519 via [`KeyPair::verify`], which does not compare
+    /// signatures with early-exit byte equality. Malformed tokens are rejected with
+    /// [`JwtError::MalformedToken`] before any slicing.
+    ///
+    /// Surrounding whitespace and embedded line breaks, as found in `.creds` files or tokens
+    /// copied from logs, are ignored. Use [`Claims::decode_strict`] to reject them.
+    pub fn decode(token: &str) -> StdResult<Claims<T>, JwtError> {
+        Self::decode_with(&normalize_token(token), None)
+    }
+
+    /// Decodes a token that must have been issued by `trusted`, whose public key is
+    /// `trusted_public`, and whose `nats.type` must match `T` as in [`Claims::decode_checked`].
+    /// Skips parsing the issuer key so one key can verify many tokens.
+    pub(crate) fn decode_trusted(
+        token: &str,
+        trusted: &KeyPair,
+        trusted_public: &str,
+    ) -> StdResult<Claims<T>, JwtError> {
+        let claims = Self::decode_with(token, Some((trusted, trusted_public)))?;
+        Self::check_claim_type(token)?;
+        Ok(claims)
+    }
 
-        let _header = ClaimsHeader::from_str(parts[0])?;
-        let payload: Claims<T> = decode_claims(parts[1])?;
-        let signature = parts[2].to_string();
+    fn decode_with(
+        token: &str,
+        trusted: Option<(&KeyPair, &str)>,
+    ) -> StdResult<Claims<T>, JwtError> {
+        let [header, claims, signature] = split_token(token)?;
+        let signing_input = token
+            .get(..header.len() + claims.len() + 1)
+            .ok_or(JwtError::MalformedToken)?;
+
+        let _header = ClaimsHeader::decode(header)?;
+        let payload: Claims<T> = decode_payload(claims)?;
         let decoded_sig = URL_SAFE_NO_PAD.decode(signature.as_bytes())?;
-        let kp = KeyPair::from_public_key(&payload.iss)?;
-        kp.verify(
-            token[0..token.len() - signature.len() - 1].as_bytes(),
-            &decoded_sig,
-        )?;
+        let issuer;
+        let kp = match trusted {
+            Some((kp, public)) if payload.iss == public => kp,
+            Some(_) => return Err(JwtError::InvalidIssuer(payload.iss)),
+            None => {
+                issuer = KeyPair::from_public_key(&payload.iss)
+                    .map_err(|_| JwtError::InvalidIssuer(payload.iss.clone()))?;
+                &issuer
+            }
+        };
+        kp.verify(signing_input.as_bytes(), &decoded_sig)
+            .map_err(|_| JwtError::SignatureVerification)?;
 
         Ok(payload)
     }
 
-    pub fn encode(&self, key_pair: &KeyPair) -> Result<String> {
-        let jwt: Jwt<T> = Jwt {
-            header: ClaimsHeader {
-                header_type: HEADER_TYPE.to_string(),
-                algorithm: HEADER_ALGORITHM.to_string(),
-            },
+    /// Decodes and verifies a token like [`Claims::decode`], but fails if the claims carry any
+    /// fields that `T` does not model. Useful for catching schema drift against nats-server.
+    pub fn decode_strict(token: &str) -> StdResult<Claims<T>, JwtError> {
+        let claims = Self::decode_with(token, None)?;
+        let raw: serde_json::Value = decode_claims(token.split('.').nth(1).unwrap_or_default())?;
+        let known = serde_json::to_value(&claims)?;
+
+        let mut unknown = Vec::new();
+        unknown_fields(&raw, &known, "", &mut unknown);
+        if !unknown.is_empty() {
+            return Err(JwtError::UnknownFields(unknown));
+        }
+
+        Ok(claims)
+    }
+
+    /// Decodes and verifies a token like [`Claims::decode`], but fails if the token's `nats.type`
+    /// is not [`Claim::CLAIM_TYPE`] for `T`, such as a user token decoded as an operator.
+    pub fn decode_checked(token: &str) -> StdResult<Claims<T>, JwtError> {
+        let token = normalize_token(token);
+        let claims = Self::decode_with(&token, None)?;
+        Self::check_claim_type(&token)?;
+        Ok(claims)
+    }
+
+    /// Fails unless the `nats.type` in the payload of `token` is [`Claim::CLAIM_TYPE`] for `T`.
+    fn check_claim_type(token: &str) -> StdResult<(), JwtError> {
+        let [_, payload, _] = split_token(token)?;
+        let probe: PayloadProbe = decode_claims(payload)?;
+        if probe.nats.claim_type != T::CLAIM_TYPE {
+            return Err(JwtError::ClaimTypeMismatch {
+                expected: T::CLAIM_TYPE,
+                found: probe.nats.claim_type,
+            });
+        }
+        Ok(())
+    }
+
+    /// Decodes a token without verifying its signature.
+    ///
+    /// Only use this when the token has already been verified or comes from a trusted source.
+    pub fn decode_unverified(token: &str) -> StdResult<Claims<T>, JwtError> {
+        let token = normalize_token(token);
+        let [header, claims, _] = split_token(&token)?;
+        let _header = ClaimsHeader::decode(header)?;
+        decode_payload(claims)
+    }
+
+    /// Parses the JSON body of a token, such as claims kept in a KV store, without any token
+    /// framing or signature verification.
+    pub fn from_json(json: &str) -> StdResult<Claims<T>, JwtError> {
+        check_version(serde_json::from_str(json)?)?;
+        Ok(serde_json::from_str(json)?)
+    }
+
+    pub fn encode(&self, key_pair: &KeyPair) -> StdResult<String, JwtError> {
+        self.encode_with_signer(key_pair)
+    }
+
+    /// Encodes and signs the claims using any [`Signer`], such as a key held in an HSM.
+    pub fn encode_with_signer(&self, signer: &dyn Signer) -> StdResult<String, JwtError> {
+        self.to_unsigned_jwt().encode(signer, JsonEncoding::Go)
+    }
+
+    /// Encodes the claims like [`Claims::encode`], but with canonical JSON: object keys sorted
+    /// and no insignificant whitespace, so the signed bytes don't depend on field order.
+    ///
+    /// This is close to, but not exactly, RFC 8785. Strings keep Go's escaping of `<`, `>`, `&`
+    /// and U+2028/U+2029, and keys are sorted by UTF-8 bytes rather than UTF-16 code units,
+    /// which only differs for non-ASCII keys. nats.go itself emits struct fields in declaration
+    /// order, so these tokens are not byte-identical to nsc's, but both verify anywhere since the
+    /// signature covers the bytes as sent. The `jti` is the same either way.
+    pub fn encode_canonical(&self, key_pair: &KeyPair) -> StdResult<String, JwtError> {
+        self.to_unsigned_jwt()
+            .encode(key_pair, JsonEncoding::Canonical)
+    }
+
+    /// Returns the `header.payload` string that [`Claims::encode`] would sign, with `iat` and
+    /// `jti` filled in as of now. Set `iss` to the public key of the external signer first, then
+    /// append `.` and the base64url (unpadded) signature over the returned bytes.
+    pub fn signing_input(&self) -> StdResult<String, JwtError> {
+        if self.iss.is_empty() {
+            return Err(JwtError::InvalidIssuer(self.iss.clone()));
+        }
+        let iat = std::time::SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        self.to_unsigned_jwt()
+            .signing_input(self.iss.clone(), iat, JsonEncoding::Go)
+    }
+
+    fn to_unsigned_jwt(&self) -> Jwt<T> {
+        Jwt {
+            header: ClaimsHeader::new(),
             payload: self.clone(),
             signature: String::new(),
-        };
+        }
+    }
 
-        jwt.encode(key_pair)
+    /// Re-issues the claims under `new_key`, for example when rotating a signing key.
+    ///
+    /// The payload, `sub`, `name` and expiry are preserved while `iss`, `iat` and `jti` are
+    /// replaced.
+    pub fn re_sign(&self, new_key: &KeyPair) -> StdResult<String, JwtError> {
+        self.encode(new_key)
     }
+
+    /// Encodes the claims like [`Claims::encode`] after sorting and de-duplicating their
+    /// permission lists, so tokens built up incrementally diff cleanly.
+    pub fn encode_normalized(&self, key_pair: &KeyPair) -> StdResult<String, JwtError> {
+        let mut claims = self.clone();
+        claims.nats.normalize_permissions();
+        claims.encode(key_pair)
+    }
+
+    /// Encodes the claims as a signed JWT string. Equivalent to [`Claims::encode`].
+    pub fn to_jwt(&self, key_pair: &KeyPair) -> StdResult<String, JwtError> {
+        self.encode(key_pair)
+    }
+
+    /// Returns a Sha512_256 base32 digest of the claims with `iat` and `jti` cleared, so claims
+    /// that differ only in when they were issued share the same hash. Unlike `jti`, the digest
+    /// covers the `nats` payload.
+    pub fn content_hash(&self) -> String {
+        let mut c = self.clone();
+        c.iat = 0;
+        c.jti = String::new();
+
+        // claims always serialize: every map in them is keyed by strings
+        let json = to_go_json(&c).expect("claims serialize to JSON");
+        BASE32_NOPAD.encode(&Sha512_256::digest(json))
+    }
+
+    /// Recomputes the `jti` and returns `true` if it matches the stored one. As in nats.go, the
+    /// `jti` covers the envelope fields (`iss`, `sub`, `exp` and so on) but not the `nats`
+    /// payload, so this only detects changes to the former.
+    pub fn verify_jti(&self) -> bool {
+        compute_jti(self).is_ok_and(|jti| jti == self.jti)
+    }
+
+    /// Returns the length in bytes of the token produced by signing the claims with `key`.
+    pub fn encoded_len(&self, key: &KeyPair) -> StdResult<usize, JwtError> {
+        Ok(self.encode(key)?.len())
+    }
+
+    /// Estimates [`Claims::encoded_len`] without a key. The issuer, issue time and `jti` are
+    /// only set when signing, so the estimate may fall short by a few dozen bytes.
+    pub(crate) fn estimated_encoded_len(&self) -> StdResult<usize, JwtError> {
+        let header = ClaimsHeader::new();
+        // an ed25519 signature is 64 bytes, or 86 characters of unpadded base64
+        Ok(encode_jwt_segment(&header)?.len() + encode_jwt_segment(self)?.len() + 86 + 2)
+    }
+
+    /// Validates the claims, recording any problems in `vr`.
+    pub fn validate(&self, vr: &mut ValidationResults) {
+        // an empty name is treated the same as an absent one
+        if let Some(name) = &self.name {
+            if name.len() > MAX_NAME_LEN {
+                vr.add_error(format!(
+                    "name is {} bytes, exceeding the maximum of {MAX_NAME_LEN}",
+                    name.len()
+                ));
+            }
+        }
+
+        if let (Some(exp), Some(nbf)) = (self.exp, self.nbf) {
+            if nbf >= exp {
+                vr.add_error(format!(
+                    "not before ({nbf}) is not earlier than expiry ({exp})"
+                ));
+            }
+        }
+        if let Some(fields) = self.nats.generic_fields() {
+            if fields.claim_type != T::CLAIM_TYPE {
+                vr.add_error(format!(
+                    "payload type {} does not match the expected {} claim type",
+                    fields.claim_type,
+                    T::CLAIM_TYPE
+                ));
+            }
+        }
+
+        // iat is zero until the claims are encoded
+        if let Some(exp) = self.exp {
+            if self.iat > 0 && exp < self.iat as i64 {
+                vr.add_error(format!("expiry ({exp}) is before issued at ({})", self.iat));
+            }
+        }
+
+        T::validate(self, vr);
+    }
+
+    /// Runs [`Claims::validate`] along with the subject and issuer nkey type checks and, when
+    /// `include_time_checks` is set, the `exp`/`nbf` checks. Pass the same flag to
+    /// [`ValidationResults::is_blocking`] when inspecting the result.
+    pub fn validate_all(&self, include_time_checks: bool) -> ValidationResults {
+        let mut vr = ValidationResults::new();
+        self.validate(&mut vr);
+
+        let (subject_types, issuer_types) = expected_key_types(&T::CLAIM_TYPE);
+        if !subject_types.is_empty()
+            && !subject_types
+                .iter()
+                .any(|kind| is_valid_public_key(&self.sub, kind.clone()))
+        {
+            vr.add_error(format!(
+                "subject {} is not a valid public key for a {} claim",
+                self.sub,
+                T::CLAIM_TYPE
+            ));
+        }
+        if !issuer_types.is_empty()
+            && !issuer_types
+                .iter()
+                .any(|kind| is_valid_public_key(&self.iss, kind.clone()))
+        {
+            vr.add_error(format!(
+                "issuer {} is not a valid public key for a {} claim",
+                self.iss,
+                T::CLAIM_TYPE
+            ));
+        }
+
+        if include_time_checks {
+            let now = unix_now();
+            if self.exp.is_some_and(|exp| exp < now) {
+                vr.add_time_check("claim is expired".to_string());
+            }
+            if self.nbf.is_some_and(|nbf| nbf > now) {
+                vr.add_time_check("claim is not yet valid".to_string());
+            }
+        }
+
+        vr
+    }
+}
+
+/// The nkey types allowed for the subject and issuer of each claim type, mirroring nats.go.
+fn expected_key_types(claim_type: &ClaimType) -> (&'static [KeyPairType], &'static [KeyPairType]) {
+    match claim_type {
+        ClaimType::Operator => (&[KeyPairType::Operator], &[KeyPairType::Operator]),
+        ClaimType::Account => (
+            &[KeyPairType::Account],
+            &[KeyPairType::Operator, KeyPairType::Account],
+        ),
+        ClaimType::User => (&[KeyPairType::User], &[KeyPairType::Account]),
+        ClaimType::Activation => (&[KeyPairType::Account], &[KeyPairType::Account]),
+        ClaimType::AuthorizationRequest => (&[KeyPairType::Account], &[KeyPairType::Server]),
+        ClaimType::AuthorizationResponse => (&[KeyPairType::User], &[KeyPairType::Account]),
+        ClaimType::Generic => (&[], &[]),
+    }
+}
+
+impl<T: Claim> Claims<T> {
+    /// Writes the envelope lines of the `Display` summaries, like those of `nsc describe`.
+    pub(crate) fn fmt_summary(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        summary_line(f, "Name", self.name.as_deref().unwrap_or("-"))?;
+        summary_line(f, "Type", T::CLAIM_TYPE)?;
+        summary_line(f, "Issuer", &self.iss)?;
+        summary_line(f, "Subject", &self.sub)?;
+        match self.exp {
+            Some(exp) => summary_line(f, "Expires", exp),
+            None => summary_line(f, "Expires", "never"),
+        }
+    }
+}
+
+/// Writes one `label: value` line of a claims summary, with the values aligned.
+pub(crate) fn summary_line(
+    f: &mut fmt::Formatter<'_>,
+    label: &str,
+    value: impl Display,
+) -> fmt::Result {
+    writeln!(f, "{:<16}{value}", format!("{label}:"))
+}
+
+#[cfg(feature = "chrono")]
+impl<T> Claims<T> {
+    pub fn issued_at(&self) -> chrono::DateTime<chrono::Utc> {
+        chrono::DateTime::from_timestamp(self.iat as i64, 0).unwrap_or_default()
+    }
+
+    pub fn expires_at(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        self.exp
+            .and_then(|exp| chrono::DateTime::from_timestamp(exp, 0))
+    }
+
+    pub fn not_before(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        self.nbf
+            .and_then(|nbf| chrono::DateTime::from_timestamp(nbf, 0))
+    }
+
+    /// Sets `exp`, truncating to whole seconds.
+    pub fn set_expires_at(&mut self, at: chrono::DateTime<chrono::Utc>) {
+        self.exp = Some(at.timestamp());
+    }
+
+    /// Sets `nbf`, truncating to whole seconds.
+    pub fn set_not_before(&mut self, at: chrono::DateTime<chrono::Utc>) {
+        self.nbf = Some(at.timestamp());
+    }
+}
+
+/// A compact binary form of decoded claims for in-memory or on-disk caches, such as a resolver
+/// holding many accounts. This is not a wire format and carries no signature, so only read back
+/// bytes the cache wrote itself.
+///
+/// CBOR is used rather than a schema-less format like postcard because the claims rely on
+/// `#[serde(flatten)]` and skipped empty fields, which only self-describing formats support.
+#[cfg(feature = "cbor")]
+impl<T> Claims<T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut buf = Vec::new();
+        ciborium::into_writer(self, &mut buf)?;
+        Ok(buf)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        Ok(ciborium::from_reader(bytes)?)
+    }
+}
+
+/// Parses a JWT string into claims **without verifying its signature**, using
+/// [`Claims::decode_unverified`]. Use [`Claims::decode`] for untrusted input.
+impl<T> FromStr for Claims<T>
+where
+    T: Claim + DeserializeOwned + Serialize + Clone,
+{
+    type Err = JwtError;
+
+    fn from_str(token: &str) -> StdResult<Self, Self::Err> {
+        Claims::decode_unverified(token)
+    }
+}
+
+/// Returns the JSON Schema of the complete claims, including the `nats` payload, for tokens of
+/// the given type.
+#[cfg(feature = "schemars")]
+pub fn schema_for_claim_type(claim_type: ClaimType) -> serde_json::Value {
+    let schema = match claim_type {
+        ClaimType::Operator => schemars::schema_for!(Claims<operator::Operator>),
+        ClaimType::Account => schemars::schema_for!(Claims<account::Account>),
+        ClaimType::User => schemars::schema_for!(Claims<user::User>),
+        ClaimType::Activation => schemars::schema_for!(Claims<activation::Activation>),
+        ClaimType::AuthorizationRequest => {
+            schemars::schema_for!(Claims<authorization::AuthRequest>)
+        }
+        ClaimType::AuthorizationResponse => {
+            schemars::schema_for!(Claims<authorization::AuthResponse>)
+        }
+        ClaimType::Generic => schemars::schema_for!(Claims<types::GenericFields>),
+    };
+    schema.to_value()
 }
 
-pub trait Claim {
-    fn validate();
+/// A source of signatures for encoded tokens.
+///
+/// Implemented for [`KeyPair`]; implement it for keys that live outside the process (an HSM or
+/// remote KMS) to sign tokens without exposing the seed.
+pub trait Signer {
+    /// The public nkey that will be recorded as the token issuer.
+    fn public_key(&self) -> String;
+
+    /// Signs `data`, returning the raw ed25519 signature bytes.
+    fn sign(&self, data: &[u8]) -> Result<Vec<u8>>;
+}
+
+impl Signer for KeyPair {
+    fn public_key(&self) -> String {
+        KeyPair::public_key(self)
+    }
+
+    fn sign(&self, data: &[u8]) -> Result<Vec<u8>> {
+        Ok(KeyPair::sign(self, data)?)
+    }
+}
+
+pub trait Claim: Sized {
+    /// The wire `type` of claims carrying this payload.
+    const CLAIM_TYPE: ClaimType;
+
+    /// Validates the claim-type specific contents of `claims`, recording any problems in `vr`.
+    fn validate(claims: &Claims<Self>, vr: &mut ValidationResults);
+
+    /// Returns the payload's generic fields, if it has them.
+    fn generic_fields(&self) -> Option<&GenericFields> {
+        None
+    }
+
+    /// Sorts and de-duplicates any permission lists in the payload. Used by
+    /// [`Claims::encode_normalized`].
+    fn normalize_permissions(&mut self) {}
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -198,72 +786,374 @@ struct Jwt<T> {
     pub signature: String,
 }
 
+/// The envelope fields of nats.go's `ClaimsData`, in its serialization order.
+#[derive(Serialize)]
+struct ClaimsData<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    aud: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    exp: Option<i64>,
+    iat: u64,
+    iss: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    nbf: Option<i64>,
+    #[serde(skip_serializing_if = "str::is_empty")]
+    sub: &'a str,
+}
+
+impl<'a, T> From<&'a Claims<T>> for ClaimsData<'a> {
+    fn from(claims: &'a Claims<T>) -> Self {
+        Self {
+            aud: claims.aud.as_deref(),
+            exp: claims.exp,
+            iat: claims.iat,
+            iss: &claims.iss,
+            name: claims.name.as_deref(),
+            nbf: claims.nbf,
+            sub: &claims.sub,
+        }
+    }
+}
+
+/// Derives the `jti` the same way nats.go does: a Sha512_256 digest of the envelope fields,
+/// excluding `jti` and the `nats` payload.
+fn compute_jti<T>(claims: &Claims<T>) -> StdResult<String, JwtError> {
+    let mut hasher = Sha512_256::new();
+    hasher.update(to_go_json(&ClaimsData::from(claims))?);
+    Ok(BASE32_NOPAD.encode(&hasher.finalize()))
+}
+
 impl<T> Jwt<T>
 where
     T: Claim + DeserializeOwned + Serialize + Clone,
 {
-    pub fn encode(&self, key: &KeyPair) -> Result<String> {
-        let hdr = encode_jwt_segment(&self.header)?;
-        let mut c = self.payload.clone();
-        c.iat = std::time::SystemTime::now()
+    pub fn encode(&self, key: &dyn Signer, encoding: JsonEncoding) -> StdResult<String, JwtError> {
+        let iat = std::time::SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .unwrap()
             .as_secs();
-        c.iss = key.public_key();
-        c.jti = "".to_string();
-
-        let encoded_claim = serde_json::to_string(&c).unwrap();
-        let mut hasher = Sha512_256::new();
-        hasher.update(encoded_claim.as_bytes());
-        let result = hasher.finalize();
-        let jti = BASE32_NOPAD.encode(&result);
-        c.jti = jti;
-
-        let claims = encode_jwt_segment(&c)?;
-        let intermediate = format!("{}.{}", hdr, claims);
-        let sig = key.sign(intermediate.as_bytes())?;
+        self.encode_at(key, iat, encoding)
+    }
+
+    /// Signs the claims as issued by `key` at `iat`.
+    fn encode_at(
+        &self,
+        key: &dyn Signer,
+        iat: u64,
+        encoding: JsonEncoding,
+    ) -> StdResult<String, JwtError> {
+        let intermediate = self.signing_input(key.public_key(), iat, encoding)?;
+        let sig = key
+            .sign(intermediate.as_bytes())
+            .map_err(JwtError::Signing)?;
         let s = URL_SAFE_NO_PAD.encode(sig);
         Ok(format!("{}.{}", intermediate, s))
     }
 
-    pub fn decode(token: &str) -> Result<Self> {
-        let parts: Vec<&str> = token.split('.').collect();
-        if parts.len() != 3 {
-            return Err(anyhow::anyhow!("Invalid JWT"));
+    /// Returns the `header.payload` string that is signed, with the claims issued by `iss` at `iat`.
+    fn signing_input(
+        &self,
+        iss: String,
+        iat: u64,
+        encoding: JsonEncoding,
+    ) -> StdResult<String, JwtError> {
+        let hdr = encoding.encode_segment(&self.header)?;
+        let c = Self::issue(&self.payload, iss, iat)?;
+
+        let claims = encoding.encode_segment(&c)?;
+        Ok(format!("{}.{}", hdr, claims))
+    }
+
+    /// Sets the issuer and issue time, then derives the `jti`.
+    fn issue(claims: &Claims<T>, iss: String, iat: u64) -> StdResult<Claims<T>, JwtError> {
+        let mut c = claims.clone();
+        c.iat = iat;
+        c.iss = iss;
+        c.jti = compute_jti(&c)?;
+        Ok(c)
+    }
+}
+
+fn unix_now() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64
+}
+
+fn encode_jwt_segment<T: Serialize>(input: &T) -> StdResult<String, JwtError> {
+    Ok(URL_SAFE_NO_PAD.encode(to_go_json(input)?))
+}
+
+/// Serializes `value` to JSON the way Go's `encoding/json` does, so signed bytes match nats.go.
+fn to_go_json<T: Serialize>(value: &T) -> StdResult<Vec<u8>, JwtError> {
+    let mut buf = Vec::new();
+    let mut ser = serde_json::Serializer::with_formatter(&mut buf, GoFormatter);
+    value.serialize(&mut ser)?;
+    Ok(buf)
+}
+
+/// How token segments are serialized before signing.
+#[derive(Debug, Clone, Copy)]
+enum JsonEncoding {
+    /// Fields in declaration order, as nats.go's `json.Marshal` emits them.
+    Go,
+    /// Object keys sorted, see [`Claims::encode_canonical`].
+    Canonical,
+}
+
+impl JsonEncoding {
+    fn encode_segment<T: Serialize>(self, input: &T) -> StdResult<String, JwtError> {
+        match self {
+            JsonEncoding::Go => encode_jwt_segment(input),
+            JsonEncoding::Canonical => {
+                encode_jwt_segment(&CanonicalJson(&serde_json::to_value(input)?))
+            }
+        }
+    }
+}
+
+/// Serializes a JSON value with object keys sorted at every level.
+struct CanonicalJson<'a>(&'a serde_json::Value);
+
+impl Serialize for CanonicalJson<'_> {
+    fn serialize<S>(&self, serializer: S) -> StdResult<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeMap;
+
+        match self.0 {
+            serde_json::Value::Object(map) => {
+                let mut entries: Vec<_> = map.iter().collect();
+                entries.sort_unstable_by(|a, b| a.0.cmp(b.0));
+                let mut ser = serializer.serialize_map(Some(entries.len()))?;
+                for (key, value) in entries {
+                    ser.serialize_entry(key, &CanonicalJson(value))?;
+                }
+                ser.end()
+            }
+            serde_json::Value::Array(values) => {
+                serializer.collect_seq(values.iter().map(CanonicalJson))
+            }
+            other => other.serialize(serializer),
+        }
+    }
+}
+
+/// A compact JSON formatter matching Go's string escaping, which escapes `<`, `>` and `&` for
+/// HTML safety and never uses the `\b`/`\f` short forms.
+struct GoFormatter;
+
+impl serde_json::ser::Formatter for GoFormatter {
+    fn write_string_fragment<W>(&mut self, writer: &mut W, fragment: &str) -> std::io::Result<()>
+    where
+        W: ?Sized + std::io::Write,
+    {
+        let mut start = 0;
+        for (i, c) in fragment.char_indices() {
+            let escaped = match c {
+                '<' => "\\u003c",
+                '>' => "\\u003e",
+                '&' => "\\u0026",
+                '\u{2028}' => "\\u2028",
+                '\u{2029}' => "\\u2029",
+                _ => continue,
+            };
+            writer.write_all(&fragment.as_bytes()[start..i])?;
+            writer.write_all(escaped.as_bytes())?;
+            start = i + c.len_utf8();
+        }
+        writer.write_all(&fragment.as_bytes()[start..])
+    }
+
+    fn write_char_escape<W>(
+        &mut self,
+        writer: &mut W,
+        char_escape: serde_json::ser::CharEscape,
+    ) -> std::io::Result<()>
+    where
+        W: ?Sized + std::io::Write,
+    {
+        use serde_json::ser::{CharEscape, CompactFormatter};
+
+        match char_escape {
+            CharEscape::Backspace => writer.write_all(b"\\u0008"),
+            CharEscape::FormFeed => writer.write_all(b"\\u000c"),
+            other => CompactFormatter.write_char_escape(writer, other),
         }
+    }
+}
+
+/// Accepts `aud` as a string or, as some tooling emits, an array holding a single string.
+fn deserialize_audience<'de, D>(deserializer: D) -> StdResult<Option<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Audience {
+        Single(String),
+        Many(Vec<String>),
+    }
+
+    match Option::<Audience>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(Audience::Single(aud)) => Ok(Some(aud)),
+        Some(Audience::Many(mut auds)) => match auds.len() {
+            0 => Ok(None),
+            1 => Ok(auds.pop()),
+            n => Err(serde::de::Error::custom(format!(
+                "expected a single audience, found {n}"
+            ))),
+        },
+    }
+}
+
+/// Reads only the `nats.type` and `nats.version` of a claims payload.
+#[derive(Deserialize)]
+struct PayloadProbe {
+    #[serde(default)]
+    nats: PayloadProbeFields,
+}
 
-        let header = decode_jwt_segment::<ClaimsHeader>(parts[0])?;
-        let payload = decode_claims(parts[1])?;
-        let signature = parts[2].to_string();
+#[derive(Deserialize)]
+struct PayloadProbeFields {
+    #[serde(rename = "type", default)]
+    claim_type: ClaimType,
+    // version 1 tokens predate the field
+    #[serde(default = "legacy_version")]
+    version: u32,
+}
 
-        Ok(Self {
-            header,
-            payload,
-            signature,
-        })
+impl Default for PayloadProbeFields {
+    fn default() -> Self {
+        Self {
+            claim_type: ClaimType::default(),
+            version: legacy_version(),
+        }
     }
 }
 
-fn encode_jwt_segment<T: Serialize>(input: &T) -> Result<String> {
-    let encoded = serde_json::to_string(input)?;
-    Ok(URL_SAFE_NO_PAD.encode(encoded.as_bytes()))
+fn legacy_version() -> u32 {
+    1
 }
 
-fn decode_jwt_segment<D: DeserializeOwned>(input: &str) -> Result<D> {
+fn check_version(probe: PayloadProbe) -> StdResult<(), JwtError> {
+    if probe.nats.version != SUPPORTED_VERSION {
+        return Err(JwtError::UnsupportedVersion(probe.nats.version));
+    }
+    Ok(())
+}
+
+/// Decodes a claims segment, rejecting payloads whose version this crate does not support.
+fn decode_payload<T: DeserializeOwned>(input: &str) -> StdResult<T, JwtError> {
     let decoded = URL_SAFE_NO_PAD.decode(input.as_bytes())?;
-    serde_json::from_slice(&decoded).context("Failed to decode JWT segment")
+    check_version(serde_json::from_slice(&decoded)?)?;
+    Ok(serde_json::from_slice(&decoded)?)
 }
 
-fn decode_claims<T: DeserializeOwned>(input: &str) -> Result<T> {
+/// Trims surrounding ASCII whitespace and removes embedded line breaks, borrowing `token` when
+/// there is nothing to remove.
+fn normalize_token(token: &str) -> Cow<'_, str> {
+    let token = token.trim_ascii();
+    if token.contains(['\r', '\n']) {
+        Cow::Owned(token.replace(['\r', '\n'], ""))
+    } else {
+        Cow::Borrowed(token)
+    }
+}
+
+/// Splits a token into its header, claims and signature segments, rejecting tokens that do not
+/// have exactly three non-empty parts.
+fn split_token(token: &str) -> StdResult<[&str; 3], JwtError> {
+    let mut parts = token.split('.');
+    match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some(header), Some(claims), Some(signature), None)
+            if !header.is_empty() && !claims.is_empty() && !signature.is_empty() =>
+        {
+            Ok([header, claims, signature])
+        }
+        _ => Err(JwtError::MalformedToken),
+    }
+}
+
+fn decode_claims<T: DeserializeOwned>(input: &str) -> StdResult<T, JwtError> {
     let decoded = URL_SAFE_NO_PAD.decode(input.as_bytes())?;
-    serde_json::from_slice(&decoded).map_err(|e| e.into())
+    Ok(serde_json::from_slice(&decoded)?)
+}
+
+/// Collects the paths of fields present in `raw` that were dropped when deserializing into
+/// `known`. Empty values are ignored since they are omitted again on serialization.
+fn unknown_fields(
+    raw: &serde_json::Value,
+    known: &serde_json::Value,
+    path: &str,
+    unknown: &mut Vec<String>,
+) {
+    use serde_json::Value;
+
+    match (raw, known) {
+        (Value::Object(raw), Value::Object(known)) => {
+            for (key, value) in raw {
+                let field = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{path}.{key}")
+                };
+                match known.get(key) {
+                    Some(known) => unknown_fields(value, known, &field, unknown),
+                    None if is_empty_value(value) => {}
+                    None => unknown.push(field),
+                }
+            }
+        }
+        (Value::Array(raw), Value::Array(known)) if raw.len() == known.len() => {
+            for (i, (raw, known)) in raw.iter().zip(known).enumerate() {
+                unknown_fields(raw, known, &format!("{path}[{i}]"), unknown);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn is_empty_value(value: &serde_json::Value) -> bool {
+    use serde_json::Value;
+
+    match value {
+        Value::Null => true,
+        Value::String(s) => s.is_empty(),
+        Value::Array(a) => a.is_empty(),
+        Value::Object(o) => o.is_empty(),
+        _ => false,
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
     use crate::authorization::AuthRequest;
+    use crate::operator::Operator;
     use crate::user::User;
+    use std::cell::RefCell;
+
+    struct RecordingSigner {
+        key: KeyPair,
+        signed: RefCell<Vec<Vec<u8>>>,
+    }
+
+    impl Signer for RecordingSigner {
+        fn public_key(&self) -> String {
+            self.key.public_key()
+        }
+
+        fn sign(&self, data: &[u8]) -> Result<Vec<u8>> {
+            self.signed.borrow_mut().push(data.to_vec());
+            Ok(self.key.sign(data)?)
+        }
+    }
 
     #[test]
     fn test_encode() {
@@ -282,6 +1172,653 @@ mod test {
         assert_eq!(dec.iss, signer.public_key());
     }
 
+    fn sign_raw(payload: &serde_json::Value, key: &KeyPair) -> String {
+        let header = ClaimsHeader::new();
+        let intermediate = format!(
+            "{}.{}",
+            encode_jwt_segment(&header).unwrap(),
+            encode_jwt_segment(payload).unwrap()
+        );
+        let sig = URL_SAFE_NO_PAD.encode(key.sign(intermediate.as_bytes()).unwrap());
+        format!("{intermediate}.{sig}")
+    }
+
+    #[test]
+    fn test_decode_strict() {
+        let signer = KeyPair::new_account();
+        let user = User::new_claims("test".to_string(), KeyPair::new_user().public_key());
+        let enc = user.encode(&signer).unwrap();
+        assert!(Claims::<User>::decode_strict(&enc).is_ok());
+
+        let mut payload = serde_json::to_value(Claims::<User>::decode(&enc).unwrap()).unwrap();
+        payload["nats"]["future_field"] = serde_json::json!("value");
+        let enc = sign_raw(&payload, &signer);
+
+        assert!(Claims::<User>::decode(&enc).is_ok());
+        let err = Claims::<User>::decode_strict(&enc).unwrap_err();
+        assert!(err.to_string().contains("nats.future_field"));
+    }
+
+    #[test]
+    fn test_signing_input() {
+        let signer = KeyPair::new_account();
+        let mut user = User::new_claims("test".to_string(), KeyPair::new_user().public_key());
+        assert!(user.signing_input().is_err());
+
+        user.iss = signer.public_key();
+        let input = user.signing_input().unwrap();
+        let sig = URL_SAFE_NO_PAD.encode(signer.sign(input.as_bytes()).unwrap());
+        let token = format!("{input}.{sig}");
+
+        let decoded = Claims::<User>::decode(&token).unwrap();
+        assert_eq!(decoded.iss, signer.public_key());
+        assert!(decoded.verify_jti());
+    }
+
+    #[test]
+    fn test_verify_jti() {
+        let signer = KeyPair::new_account();
+        let user = User::new_claims("test".to_string(), KeyPair::new_user().public_key());
+        let mut decoded = Claims::<User>::decode(&user.encode(&signer).unwrap()).unwrap();
+        assert!(decoded.verify_jti());
+
+        decoded.name = Some("tampered".to_string());
+        assert!(!decoded.verify_jti());
+        assert!(!user.verify_jti());
+    }
+
+    #[test]
+    fn test_content_hash() {
+        let mut a = User::new_claims("test".to_string(), KeyPair::new_user().public_key());
+        a.iat = 1000;
+        a.jti = "abc".to_string();
+        let mut b = a.clone();
+        b.iat = 2000;
+        b.jti = "def".to_string();
+        assert_eq!(a.content_hash(), b.content_hash());
+
+        b.nats.generic_fields.add_tag("prod");
+        assert_ne!(a.content_hash(), b.content_hash());
+    }
+
+    #[test]
+    fn test_decode_tolerates_whitespace() {
+        let signer = KeyPair::new_account();
+        let user = User::new_claims("test".to_string(), KeyPair::new_user().public_key());
+        let enc = user.encode(&signer).unwrap();
+        let (head, tail) = enc.split_at(enc.len() / 2);
+        let loose = format!("  {head}\n{tail}\n");
+
+        let decoded = Claims::<User>::decode(&loose).unwrap();
+        assert_eq!(decoded.sub, user.sub);
+        assert!(Claims::<User>::decode_checked(&loose).is_ok());
+        assert!(Claims::<User>::decode_unverified(&loose).is_ok());
+        assert!(Claims::<User>::decode_strict(&loose).is_err());
+    }
+
+    #[test]
+    fn test_encode_with_signer() {
+        let signer = RecordingSigner {
+            key: KeyPair::new_account(),
+            signed: RefCell::new(Vec::new()),
+        };
+        let user = User::new_claims("test".to_string(), KeyPair::new_user().public_key());
+        let enc = user.encode_with_signer(&signer).unwrap();
+
+        let signed = signer.signed.borrow();
+        assert_eq!(signed.len(), 1);
+        let (signing_input, _) = enc.rsplit_once('.').unwrap();
+        assert_eq!(signed[0], signing_input.as_bytes());
+
+        let dec = Claims::<User>::decode(&enc).unwrap();
+        assert_eq!(dec.iss, signer.key.public_key());
+    }
+
+    #[test]
+    fn test_parse() {
+        let user_key = KeyPair::new_user();
+        let signer = KeyPair::new_account();
+        let user = User::new_claims("test".to_string(), user_key.public_key());
+        let enc = user.to_jwt(&signer).unwrap();
+
+        let parsed: Claims<User> = enc.parse().unwrap();
+        assert_eq!(parsed.sub, user_key.public_key());
+        assert_eq!(parsed.iss, signer.public_key());
+
+        assert!("not.a.jwt".parse::<Claims<User>>().is_err());
+        assert!("missing-segments".parse::<Claims<User>>().is_err());
+    }
+
+    #[test]
+    fn test_validate_name_length() {
+        let mut user = User::new_claims("a".repeat(MAX_NAME_LEN), KeyPair::new_user().public_key());
+        let mut vr = ValidationResults::new();
+        user.validate(&mut vr);
+        assert!(!vr.is_blocking(false));
+
+        user.name = Some("a".repeat(MAX_NAME_LEN + 1));
+        let mut vr = ValidationResults::new();
+        user.validate(&mut vr);
+        assert!(vr.is_blocking(false));
+    }
+
+    #[test]
+    fn test_validate_nbf_after_exp() {
+        let mut user = User::new_claims("test".to_string(), KeyPair::new_user().public_key());
+        user.nbf = Some(2000);
+        user.exp = Some(1000);
+        let mut vr = ValidationResults::new();
+        user.validate(&mut vr);
+        assert!(vr.is_blocking(false));
+
+        user.nbf = Some(500);
+        let mut vr = ValidationResults::new();
+        user.validate(&mut vr);
+        assert!(!vr.is_blocking(false));
+    }
+
+    #[test]
+    fn test_validate_exp_before_iat() {
+        let mut user = User::new_claims("test".to_string(), KeyPair::new_user().public_key());
+        user.iat = 2000;
+        user.exp = Some(1000);
+        let mut vr = ValidationResults::new();
+        user.validate(&mut vr);
+        assert!(vr.is_blocking(false));
+
+        user.exp = Some(3000);
+        let mut vr = ValidationResults::new();
+        user.validate(&mut vr);
+        assert!(!vr.is_blocking(false));
+    }
+
+    #[test]
+    fn test_validate_claim_type() {
+        let signer = KeyPair::new_account();
+        let user = User::new_claims("test".to_string(), KeyPair::new_user().public_key());
+        let token = user.encode(&signer).unwrap();
+
+        let mut vr = ValidationResults::new();
+        Claims::<User>::decode(&token).unwrap().validate(&mut vr);
+        assert!(vr.is_empty());
+
+        let mut vr = ValidationResults::new();
+        Claims::<Operator>::decode(&token)
+            .unwrap()
+            .validate(&mut vr);
+        assert!(vr.is_blocking(false));
+    }
+
+    #[test]
+    fn test_validate_empty_name() {
+        let user = User::new_claims(String::new(), KeyPair::new_user().public_key());
+        let mut vr = ValidationResults::new();
+        user.validate(&mut vr);
+        assert!(!vr.is_blocking(false));
+    }
+
+    #[test]
+    fn test_nsc_compatible_encoding() {
+        // the payload segment of a user JWT issued by nsc, taken from the connect_opts of the
+        // authorization request fixture below
+        let nsc_payload = "eyJqdGkiOiJUUkdDNVJKWlpLTkpZUEJLRlNNVFFNU05YR0hZTzVENkFHUDU3WlNYUVpKMzJCUVNXTEpRIiwiaWF0IjoxNzI0MDkxNDQ3LCJpc3MiOiJBRDVKWEEyV1RaMk5ZSlBPUzZLUUdSUklNRjNSU0NaR0tTVUlLWVc0UUVVUlhVUk1GT0VDM0xMUSIsIm5hbWUiOiJ3YXNtQ2xvdWQgVXNlciBBdXRoLXJlZ2lzdHJhdGlvbiIsInN1YiI6IlVDQjdHNEpXQ0xVSUpFNzU1MklSVTNFVUNQWUhTREdJRUJBTk5RMkRMUFM0R0hLTkZPUVpPUlVBIiwibmF0cyI6eyJwdWIiOnsiZGVueSI6WyJcdTAwM2UiXX0sInN1YiI6eyJkZW55IjpbIlx1MDAzZSJdfSwic3VicyI6LTEsImRhdGEiOi0xLCJwYXlsb2FkIjotMSwiaXNzdWVyX2FjY291bnQiOiJBQ1ZVS1NBVkRKVjY1QVpMTlJQU0tGSlBZMjJXTlJaSVhGVU9SWFhLVlkyTEhYTTJKTUtMN0c0RiIsInR5cGUiOiJ1c2VyIiwidmVyc2lvbiI6Mn19";
+
+        let mut user = User::new_claims(
+            "wasmCloud User Auth-registration".to_string(),
+            "UCB7G4JWCLUIJE7552IRU3EUCPYHSDGIEBANNQ2DLPS4GHKNFOQZORUA".to_string(),
+        );
+        user.nats.issuer_account =
+            Some("ACVUKSAVDJV65AZLNRPSKFJPY22WNRZIXFUORXXKVY2LHXM2JMKL7G4F".to_string());
+        user.nats.permissions.permissions.publish.deny = vec![">".to_string()];
+        user.nats.permissions.permissions.subscribe.deny = vec![">".to_string()];
+
+        let issued = Jwt::issue(
+            &user,
+            "AD5JXA2WTZ2NYJPOS6KQGRRIMF3RSCZGKSUIKYW4QEURXURMFOEC3LLQ".to_string(),
+            1724091447,
+        )
+        .unwrap();
+        assert_eq!(
+            issued.jti,
+            "TRGC5RJZZKNJYPBKFSMTQMSNXGHYO5D6AGP57ZSXQZJ32BQSWLJQ"
+        );
+        assert_eq!(encode_jwt_segment(&issued).unwrap(), nsc_payload);
+    }
+
+    #[test]
+    fn test_nsc_compatible_token() {
+        // the full user JWT issued by nsc, from the connect_opts of the authorization request
+        // fixture below, sent by the NATS CLI (nats.go 1.33.1) to nats-server 2.10.18. Its
+        // signing seed was not recorded, so instead of re-signing, the bytes this crate signs
+        // must match nsc's exactly for nsc's signature to verify.
+        let nsc_token = concat!(
+            "eyJ0eXAiOiJKV1QiLCJhbGciOiJlZDI1NTE5LW5rZXkifQ.eyJqdGkiOiJUUkdDNVJKWlpLTkpZUEJLRlNNVFFNU",
+            "05YR0hZTzVENkFHUDU3WlNYUVpKMzJCUVNXTEpRIiwiaWF0IjoxNzI0MDkxNDQ3LCJpc3MiOiJBRDVKWEEyV1RaM",
+            "k5ZSlBPUzZLUUdSUklNRjNSU0NaR0tTVUlLWVc0UUVVUlhVUk1GT0VDM0xMUSIsIm5hbWUiOiJ3YXNtQ2xvdWQgV",
+            "XNlciBBdXRoLXJlZ2lzdHJhdGlvbiIsInN1YiI6IlVDQjdHNEpXQ0xVSUpFNzU1MklSVTNFVUNQWUhTREdJRUJBT",
+            "k5RMkRMUFM0R0hLTkZPUVpPUlVBIiwibmF0cyI6eyJwdWIiOnsiZGVueSI6WyJcdTAwM2UiXX0sInN1YiI6eyJkZ",
+            "W55IjpbIlx1MDAzZSJdfSwic3VicyI6LTEsImRhdGEiOi0xLCJwYXlsb2FkIjotMSwiaXNzdWVyX2FjY291bnQiO",
+            "iJBQ1ZVS1NBVkRKVjY1QVpMTlJQU0tGSlBZMjJXTlJaSVhGVU9SWFhLVlkyTEhYTTJKTUtMN0c0RiIsInR5cGUiO",
+            "iJ1c2VyIiwidmVyc2lvbiI6Mn19.",
+            "1_DxwilYeT2JkCKV1H0Oykp9Upums9F_RM0E8e6W-XxEtGXgYN-boXNkokIC6XjE5dkkSBZSVXz53p5JFK4UDQ",
+        );
+
+        let mut user = User::new_claims(
+            "wasmCloud User Auth-registration".to_string(),
+            "UCB7G4JWCLUIJE7552IRU3EUCPYHSDGIEBANNQ2DLPS4GHKNFOQZORUA".to_string(),
+        );
+        user.nats.issuer_account =
+            Some("ACVUKSAVDJV65AZLNRPSKFJPY22WNRZIXFUORXXKVY2LHXM2JMKL7G4F".to_string());
+        user.nats.permissions.permissions.publish.deny = vec![">".to_string()];
+        user.nats.permissions.permissions.subscribe.deny = vec![">".to_string()];
+
+        let issuer = "AD5JXA2WTZ2NYJPOS6KQGRRIMF3RSCZGKSUIKYW4QEURXURMFOEC3LLQ";
+        let signing_input = user
+            .to_unsigned_jwt()
+            .signing_input(issuer.to_string(), 1724091447, JsonEncoding::Go)
+            .unwrap();
+        let (nsc_input, nsc_sig) = nsc_token.rsplit_once('.').unwrap();
+        assert_eq!(signing_input, nsc_input);
+
+        let nsc_sig = URL_SAFE_NO_PAD.decode(nsc_sig).unwrap();
+        assert!(KeyPair::from_public_key(issuer)
+            .unwrap()
+            .verify(signing_input.as_bytes(), &nsc_sig)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_canonical_encoding() {
+        // the nsc payload from `test_nsc_compatible_encoding`, with keys sorted
+        let canonical = concat!(
+            r#"{"iat":1724091447,"iss":"AD5JXA2WTZ2NYJPOS6KQGRRIMF3RSCZGKSUIKYW4QEURXURMFOEC3LLQ","#,
+            r#""jti":"TRGC5RJZZKNJYPBKFSMTQMSNXGHYO5D6AGP57ZSXQZJ32BQSWLJQ","#,
+            r#""name":"wasmCloud User Auth-registration","#,
+            r#""nats":{"data":-1,"issuer_account":"ACVUKSAVDJV65AZLNRPSKFJPY22WNRZIXFUORXXKVY2LHXM2JMKL7G4F","#,
+            r#""payload":-1,"pub":{"deny":["\u003e"]},"sub":{"deny":["\u003e"]},"subs":-1,"#,
+            r#""type":"user","version":2},"#,
+            r#""sub":"UCB7G4JWCLUIJE7552IRU3EUCPYHSDGIEBANNQ2DLPS4GHKNFOQZORUA"}"#
+        );
+
+        let mut user = User::new_claims(
+            "wasmCloud User Auth-registration".to_string(),
+            "UCB7G4JWCLUIJE7552IRU3EUCPYHSDGIEBANNQ2DLPS4GHKNFOQZORUA".to_string(),
+        );
+        user.nats.issuer_account =
+            Some("ACVUKSAVDJV65AZLNRPSKFJPY22WNRZIXFUORXXKVY2LHXM2JMKL7G4F".to_string());
+        user.nats.permissions.permissions.publish.deny = vec![">".to_string()];
+        user.nats.permissions.permissions.subscribe.deny = vec![">".to_string()];
+        let issued = Jwt::issue(
+            &user,
+            "AD5JXA2WTZ2NYJPOS6KQGRRIMF3RSCZGKSUIKYW4QEURXURMFOEC3LLQ".to_string(),
+            1724091447,
+        )
+        .unwrap();
+        assert_eq!(
+            JsonEncoding::Canonical.encode_segment(&issued).unwrap(),
+            URL_SAFE_NO_PAD.encode(canonical)
+        );
+
+        let signer = KeyPair::new_account();
+        let token = user.encode_canonical(&signer).unwrap();
+        let decoded = Claims::<User>::decode(&token).unwrap();
+        assert_eq!(decoded.sub, user.sub);
+        assert_eq!(
+            decoded.payload().issuer_account,
+            user.payload().issuer_account
+        );
+        assert!(decoded.verify_jti());
+    }
+
+    #[test]
+    fn test_encode_normalized() {
+        let signer = KeyPair::new_account();
+        let mut user = User::new_claims("test".to_string(), KeyPair::new_user().public_key());
+        user.nats.permissions.permissions.publish.allow =
+            vec!["b".to_string(), "a".to_string(), "a".to_string()];
+
+        let decoded = Claims::<User>::decode(&user.encode_normalized(&signer).unwrap()).unwrap();
+        assert_eq!(
+            decoded.nats.permissions.permissions.publish.allow,
+            ["a", "b"]
+        );
+        assert_eq!(user.nats.permissions.permissions.publish.allow.len(), 3);
+    }
+
+    #[test]
+    fn test_remaining_ttl() {
+        let mut user = User::new_claims("test".to_string(), KeyPair::new_user().public_key());
+        assert_eq!(user.remaining_ttl(), None);
+
+        user.exp = Some(unix_now() + 60);
+        let ttl = user.remaining_ttl().unwrap();
+        assert!(ttl > Duration::from_secs(55) && ttl <= Duration::from_secs(60));
+
+        user.exp = Some(unix_now() - 60);
+        assert_eq!(user.remaining_ttl(), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn test_re_sign() {
+        let old_key = KeyPair::new_account();
+        let new_key = KeyPair::new_account();
+        let mut user = User::new_claims("test".to_string(), KeyPair::new_user().public_key());
+        user.exp = Some(4102444800);
+        let decoded = Claims::<User>::decode(&user.encode(&old_key).unwrap()).unwrap();
+
+        let token = decoded.re_sign(&new_key).unwrap();
+        let resigned = Claims::<User>::decode(&token).unwrap();
+        assert_eq!(resigned.iss, new_key.public_key());
+        assert_eq!(resigned.sub, decoded.sub);
+        assert_eq!(resigned.name, decoded.name);
+        assert_eq!(resigned.exp, decoded.exp);
+
+        let (signing_input, sig) = token.rsplit_once('.').unwrap();
+        let sig = URL_SAFE_NO_PAD.decode(sig).unwrap();
+        assert!(new_key.verify(signing_input.as_bytes(), &sig).is_ok());
+        assert!(old_key.verify(signing_input.as_bytes(), &sig).is_err());
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        let signer = KeyPair::new_account();
+        let user = User::new_claims("test".to_string(), KeyPair::new_user().public_key());
+        let enc = user.encode(&signer).unwrap();
+
+        let (truncated, _) = enc.rsplit_once('.').unwrap();
+        assert!(matches!(
+            Claims::<User>::decode(truncated),
+            Err(JwtError::MalformedToken)
+        ));
+
+        let (_, rest) = enc.split_once('.').unwrap();
+        assert!(matches!(
+            Claims::<User>::decode(&format!("not*base64.{rest}")),
+            Err(JwtError::Base64(_))
+        ));
+        let header = URL_SAFE_NO_PAD.encode("not json");
+        assert!(matches!(
+            Claims::<User>::decode(&format!("{header}.{rest}")),
+            Err(JwtError::Json(_))
+        ));
+        assert!(matches!(
+            ClaimsHeader::from_token(&format!("{header}.{rest}")),
+            Err(JwtError::Json(_))
+        ));
+
+        let header = URL_SAFE_NO_PAD.encode(r#"{"typ":"JWT","alg":"HS256"}"#);
+        assert!(matches!(
+            Claims::<User>::decode(&format!("{header}.{rest}")),
+            Err(JwtError::UnsupportedAlgorithm(alg)) if alg == "HS256"
+        ));
+
+        let forged = format!(
+            "{}.{}",
+            enc.rsplit_once('.').unwrap().0,
+            URL_SAFE_NO_PAD.encode([0u8; 64])
+        );
+        assert!(matches!(
+            Claims::<User>::decode(&forged),
+            Err(JwtError::SignatureVerification)
+        ));
+
+        let err: anyhow::Error = Claims::<User>::decode(truncated).unwrap_err().into();
+        assert_eq!(err.to_string(), "malformed token");
+    }
+
+    #[test]
+    fn test_decode_extra_header_fields() {
+        let signer = KeyPair::new_account();
+        let user = User::new_claims("test".to_string(), KeyPair::new_user().public_key());
+        let enc = user.encode(&signer).unwrap();
+        let (_, payload) = enc.rsplit_once('.').unwrap().0.split_once('.').unwrap();
+
+        let header = URL_SAFE_NO_PAD.encode(r#"{"typ":"JWT","alg":"ed25519-nkey","kid":"key-1"}"#);
+        let signing_input = format!("{header}.{payload}");
+        let sig = URL_SAFE_NO_PAD.encode(signer.sign(signing_input.as_bytes()).unwrap());
+        let token = format!("{signing_input}.{sig}");
+
+        let decoded = Claims::<User>::decode(&token).unwrap();
+        assert_eq!(decoded.sub, user.sub);
+
+        let header = ClaimsHeader::from_token(&token).unwrap();
+        assert_eq!(header.headers_raw().len(), 1);
+        assert_eq!(header.headers_raw()["kid"], "key-1");
+        assert!(ClaimsHeader::from_token(&enc)
+            .unwrap()
+            .headers_raw()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_audience_string_or_array() {
+        let user = User::new_claims("test".to_string(), KeyPair::new_user().public_key());
+        let mut json = serde_json::to_value(&user).unwrap();
+
+        json["aud"] = serde_json::json!("x");
+        let claims = Claims::<User>::from_json(&json.to_string()).unwrap();
+        assert_eq!(claims.aud.as_deref(), Some("x"));
+
+        json["aud"] = serde_json::json!(["x"]);
+        let claims = Claims::<User>::from_json(&json.to_string()).unwrap();
+        assert_eq!(claims.aud.as_deref(), Some("x"));
+        assert_eq!(serde_json::to_value(&claims).unwrap()["aud"], "x");
+
+        json["aud"] = serde_json::json!(["x", "y"]);
+        assert!(Claims::<User>::from_json(&json.to_string()).is_err());
+
+        json.as_object_mut().unwrap().remove("aud");
+        let claims = Claims::<User>::from_json(&json.to_string()).unwrap();
+        assert_eq!(claims.aud, None);
+    }
+
+    #[test]
+    fn test_claims_builder() {
+        let account = KeyPair::new_account();
+        let user_key = KeyPair::new_user();
+        let mut payload = User::default();
+        payload.permissions.bearer_token = Some(true);
+
+        let claims = Claims::<User>::builder()
+            .name("test")
+            .subject(user_key.public_key())
+            .audience("NATS")
+            .expires_in(Duration::from_secs(3600))
+            .not_before_in(Duration::from_secs(60))
+            .payload(payload)
+            .build();
+        let now = unix_now();
+        assert!(claims.exp.unwrap() >= now + 3599);
+        assert!(claims.nbf.unwrap() >= now + 59);
+
+        let decoded = Claims::<User>::decode(&claims.encode(&account).unwrap()).unwrap();
+        assert_eq!(decoded.name.as_deref(), Some("test"));
+        assert_eq!(decoded.sub, user_key.public_key());
+        assert_eq!(decoded.aud.as_deref(), Some("NATS"));
+        assert_eq!(decoded.exp, claims.exp);
+        assert!(decoded.nats.is_bearer());
+    }
+
+    #[test]
+    fn test_validate_all() {
+        let account = KeyPair::new_account();
+        let mut user = User::new_claims("test".to_string(), KeyPair::new_user().public_key());
+        user.nbf = Some(unix_now() + 3600);
+        let user = Claims::<User>::decode(&user.encode(&account).unwrap()).unwrap();
+
+        assert!(!user.validate_all(false).is_blocking(false));
+        assert!(user.validate_all(true).is_blocking(true));
+
+        // issued two hours ago and expired an hour ago
+        let mut user = User::new_claims("test".to_string(), KeyPair::new_user().public_key());
+        user.exp = Some(unix_now() - 3600);
+        let token = user
+            .to_unsigned_jwt()
+            .encode_at(&account, (unix_now() - 7200) as u64, JsonEncoding::Go)
+            .unwrap();
+        let user = Claims::<User>::decode(&token).unwrap();
+
+        assert!(!user.validate_all(false).is_blocking(false));
+        assert!(user.validate_all(true).is_blocking(true));
+
+        let operator = KeyPair::new_operator();
+        let user = User::new_claims("test".to_string(), KeyPair::new_user().public_key());
+        let user = Claims::<User>::decode(&user.encode(&operator).unwrap()).unwrap();
+        assert!(user.validate_all(false).is_blocking(false));
+    }
+
+    #[test]
+    fn test_decode_unsupported_version() {
+        let signer = KeyPair::new_account();
+        let user = User::new_claims("test".to_string(), KeyPair::new_user().public_key());
+        let mut payload = serde_json::to_value(&user).unwrap();
+        payload["iss"] = serde_json::Value::String(signer.public_key());
+        payload["nats"]["version"] = serde_json::Value::from(1);
+        let token = sign_raw(&payload, &signer);
+
+        assert!(matches!(
+            Claims::<User>::decode(&token),
+            Err(JwtError::UnsupportedVersion(1))
+        ));
+        assert!(matches!(
+            Claims::<User>::decode_unverified(&token),
+            Err(JwtError::UnsupportedVersion(1))
+        ));
+        assert!(matches!(
+            Claims::<User>::from_json(&payload.to_string()),
+            Err(JwtError::UnsupportedVersion(1))
+        ));
+
+        payload["nats"].as_object_mut().unwrap().remove("version");
+        assert!(matches!(
+            Claims::<User>::from_json(&payload.to_string()),
+            Err(JwtError::UnsupportedVersion(1))
+        ));
+    }
+
+    #[test]
+    fn test_decode_checked() {
+        let signer = KeyPair::new_account();
+        let user = User::new_claims("test".to_string(), KeyPair::new_user().public_key());
+        let enc = user.encode(&signer).unwrap();
+
+        assert!(Claims::<User>::decode_checked(&enc).is_ok());
+        assert!(Claims::<Operator>::decode(&enc).is_ok());
+        assert!(matches!(
+            Claims::<Operator>::decode_checked(&enc),
+            Err(JwtError::ClaimTypeMismatch {
+                expected: ClaimType::Operator,
+                found: ClaimType::User,
+            })
+        ));
+    }
+
+    #[test]
+    fn test_decode_malformed_does_not_panic() {
+        let signer = KeyPair::new_account();
+        let user = User::new_claims("test".to_string(), KeyPair::new_user().public_key());
+        let enc = user.encode(&signer).unwrap();
+        let (header, rest) = enc.split_once('.').unwrap();
+        let (claims, signature) = rest.split_once('.').unwrap();
+
+        for token in ["", ".", "..", "...", "a..", ".a.", "..a"] {
+            assert!(matches!(
+                Claims::<User>::decode(token),
+                Err(JwtError::MalformedToken)
+            ));
+        }
+        for token in [
+            format!("{header}.{claims}."),
+            format!("{header}..{signature}"),
+            format!(".{claims}.{signature}"),
+            format!("{enc}.{signature}"),
+        ] {
+            assert!(matches!(
+                Claims::<User>::decode(&token),
+                Err(JwtError::MalformedToken)
+            ));
+            assert!(Claims::<User>::decode_unverified(&token).is_err());
+        }
+
+        let oversized = format!("{header}.{claims}.{}", signature.repeat(8));
+        assert!(Claims::<User>::decode(&oversized).is_err());
+
+        for len in 0..enc.len() {
+            if let Some(prefix) = enc.get(..len) {
+                assert!(Claims::<User>::decode(prefix).is_err());
+                assert!(Claims::<User>::decode_strict(prefix).is_err());
+            }
+        }
+    }
+
+    #[cfg(feature = "schemars")]
+    #[test]
+    fn test_schema_for_claim_type() {
+        let schema = schema_for_claim_type(ClaimType::User);
+        let properties = schema["properties"].as_object().unwrap();
+        for property in ["sub", "iss", "nats"] {
+            assert!(properties.contains_key(property), "missing {property}");
+        }
+
+        let schema = schema_for_claim_type(ClaimType::Account);
+        assert!(schema["$defs"]["Account"]["properties"]
+            .as_object()
+            .unwrap()
+            .contains_key("exports"));
+
+        // durations are serialized as integer nanoseconds, like Go's time.Duration
+        let defs = &schema["$defs"];
+        assert_eq!(
+            defs["Export"]["properties"]["response_threshold"]["type"],
+            serde_json::json!(["integer", "null"])
+        );
+        assert_eq!(
+            defs["ResponsePermission"]["properties"]["ttl"]["type"],
+            "integer"
+        );
+    }
+
+    #[cfg(feature = "cbor")]
+    #[test]
+    fn test_bytes_round_trip() {
+        let operator = KeyPair::new_operator();
+        let mut account =
+            account::Account::new_claims("test".to_string(), KeyPair::new_account().public_key());
+        account.nats.set_default_publish_allow(["orders.>"]);
+        account.nats.add_export(types::Export {
+            subject: "svc.orders".to_string(),
+            export_type: Some(types::ExportType::Service),
+            ..Default::default()
+        });
+        let decoded =
+            Claims::<account::Account>::decode(&account.encode(&operator).unwrap()).unwrap();
+
+        let bytes = decoded.to_bytes().unwrap();
+        let restored = Claims::<account::Account>::from_bytes(&bytes).unwrap();
+        assert_eq!(
+            serde_json::to_value(&restored).unwrap(),
+            serde_json::to_value(&decoded).unwrap()
+        );
+        assert!(Claims::<account::Account>::from_bytes(&bytes[..bytes.len() / 2]).is_err());
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_chrono_timestamps() {
+        let mut user = User::new_claims("test".to_string(), KeyPair::new_user().public_key());
+        user.iat = 1724091447;
+        assert_eq!(user.issued_at().timestamp(), 1724091447);
+        assert_eq!(user.expires_at(), None);
+
+        let exp = chrono::DateTime::from_timestamp(1724095784, 0).unwrap();
+        user.set_expires_at(exp);
+        user.set_not_before(exp - chrono::Duration::hours(1));
+        assert_eq!(user.exp, Some(1724095784));
+        assert_eq!(user.nbf, Some(1724092184));
+        assert_eq!(user.expires_at(), Some(exp));
+        assert_eq!(user.not_before().unwrap().timestamp(), 1724092184);
+    }
+
     #[test]
     fn test_decode() {
         let token = r#"
@@ -327,5 +1864,17 @@ mod test {
 
         let auth: Claims<AuthRequest> = serde_json::from_str(token).unwrap();
         assert_ne!(auth.payload().client_info.user, "");
+
+        let auth = Claims::<AuthRequest>::from_json(token).unwrap();
+        assert_eq!(auth.aud.as_deref(), Some(audiences::REQUEST));
+        assert_eq!(auth.aud.as_deref(), Some(AuthRequest::EXPECTED_AUDIENCE));
+        assert_eq!(
+            auth.payload().user_nkey,
+            "UCN6UGLQZQB5GXHQOQOSMXYKN4PRMB7PSXVVEDIAWAFNBO25NOUK6DCU"
+        );
+        assert!(matches!(
+            Claims::<AuthRequest>::from_json("{"),
+            Err(JwtError::Json(_))
+        ));
     }
 }
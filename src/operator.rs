@@ -1,8 +1,19 @@
-use crate::{types::GenericFields, Claim, ClaimType, Claims};
+use crate::{
+    account::Account,
+    summary_line,
+    types::GenericFields,
+    validation::{is_valid_public_key, ValidationResults},
+    Claim, ClaimType, Claims,
+};
+use anyhow::Context;
 use derive_builder::Builder;
+use nkeys::{KeyPair, KeyPairType};
 use serde::{Deserialize, Serialize};
+use std::fmt;
+use url::Url;
 
 #[derive(Debug, Serialize, Deserialize, Clone, Builder)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[builder(setter(into), default)]
 pub struct Operator {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -26,7 +37,7 @@ impl Default for Operator {
     fn default() -> Self {
         Self {
             generic_fields: GenericFields {
-                claim_type: ClaimType::Operator,
+                claim_type: Self::CLAIM_TYPE,
                 ..Default::default()
             },
             signing_keys: None,
@@ -39,8 +50,91 @@ impl Default for Operator {
     }
 }
 
+impl Claims<Operator> {
+    /// Signs the claims with `key`, the operator's identity key, setting `sub` to its public
+    /// key so the resulting token is self-signed (`iss == sub`).
+    pub fn self_sign(&mut self, key: &KeyPair) -> anyhow::Result<String> {
+        let public = key.public_key();
+        if key.key_pair_type() != KeyPairType::Operator {
+            return Err(anyhow::anyhow!("{public} is not an operator key"));
+        }
+        self.sub = public;
+
+        Ok(self.encode(key)?)
+    }
+}
+
+impl fmt::Display for Claims<Operator> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_summary(f)?;
+        let operator = &self.nats;
+        summary_line(
+            f,
+            "Signing keys",
+            operator.signing_keys.as_ref().map_or(0, Vec::len),
+        )?;
+        summary_line(
+            f,
+            "System account",
+            operator.system_account.as_deref().unwrap_or("-"),
+        )?;
+        summary_line(f, "Resolver", operator.resolver_url().unwrap_or("-"))
+    }
+}
+
+/// Schemes allowed for `operator_service_urls`.
+const SERVICE_URL_SCHEMES: &[&str] = &["nats", "tls"];
+/// Schemes allowed for `account_server_url`.
+const ACCOUNT_SERVER_URL_SCHEMES: &[&str] = &["http", "https", "nats"];
+
+fn check_url(url: &str, kind: &str, schemes: &[&str]) -> anyhow::Result<()> {
+    let parsed = Url::parse(url).with_context(|| format!("invalid {kind} url {url}"))?;
+    if !schemes.contains(&parsed.scheme()) {
+        return Err(anyhow::anyhow!(
+            "{kind} url {url} must use one of the schemes {}",
+            schemes.join(", ")
+        ));
+    }
+    Ok(())
+}
+
 impl Claim for Operator {
-    fn validate() {}
+    const CLAIM_TYPE: ClaimType = ClaimType::Operator;
+
+    fn validate(claims: &Claims<Self>, vr: &mut ValidationResults) {
+        if !claims.iss.is_empty()
+            && claims.iss != claims.sub
+            && !claims.nats.has_signing_key(&claims.iss)
+        {
+            vr.add_warning(format!(
+                "operator {} is issued by {}, which is neither the operator nor one of its signing keys",
+                claims.sub, claims.iss
+            ));
+        }
+
+        if let Some(version) = &claims.nats.assert_server_version {
+            if parse_server_version(version).is_none() {
+                vr.add_error(format!(
+                    "assert_server_version {version} is not a valid semantic version"
+                ));
+            }
+        }
+
+        if let Some(url) = &claims.nats.account_server_url {
+            if let Err(e) = check_url(url, "account server", ACCOUNT_SERVER_URL_SCHEMES) {
+                vr.add_error(e.to_string());
+            }
+        }
+        for url in claims.nats.operator_service_urls.iter().flatten() {
+            if let Err(e) = check_url(url, "operator service", SERVICE_URL_SCHEMES) {
+                vr.add_error(e.to_string());
+            }
+        }
+    }
+
+    fn generic_fields(&self) -> Option<&GenericFields> {
+        Some(&self.generic_fields)
+    }
 }
 
 impl Operator {
@@ -51,4 +145,451 @@ impl Operator {
         claim.sub = nkey;
         claim
     }
+
+    /// Creates claims for the public key of `key`, which must be an operator key.
+    pub fn new_claims_for(
+        name: impl Into<String>,
+        key: &KeyPair,
+    ) -> anyhow::Result<Claims<Operator>> {
+        if key.key_pair_type() != KeyPairType::Operator {
+            return Err(anyhow::anyhow!(
+                "{} is not an operator key",
+                key.public_key()
+            ));
+        }
+        Ok(Self::new_claims(name.into(), key.public_key()))
+    }
+
+    /// Adds an operator signing key, ignoring keys that are already present.
+    ///
+    /// Returns an error without modifying the claim if `key` is not a public operator nkey.
+    pub fn add_signing_key(&mut self, key: impl Into<String>) -> anyhow::Result<()> {
+        let key = key.into();
+        if !is_valid_public_key(&key, KeyPairType::Operator) {
+            return Err(anyhow::anyhow!("{key} is not a valid operator public key"));
+        }
+
+        let keys = self.signing_keys.get_or_insert_with(Vec::new);
+        if !keys.contains(&key) {
+            keys.push(key);
+        }
+        Ok(())
+    }
+
+    /// Removes an operator signing key, returning `true` if it was present.
+    pub fn remove_signing_key(&mut self, key: &str) -> bool {
+        let Some(keys) = self.signing_keys.as_mut() else {
+            return false;
+        };
+        let len = keys.len();
+        keys.retain(|k| k != key);
+        let removed = keys.len() != len;
+        if keys.is_empty() {
+            self.signing_keys = None;
+        }
+        removed
+    }
+
+    /// Returns `true` if `key` is one of the operator's signing keys.
+    pub fn has_signing_key(&self, key: &str) -> bool {
+        self.signing_keys
+            .as_ref()
+            .is_some_and(|keys| keys.iter().any(|k| k == key))
+    }
+
+    /// Designates the system account, rejecting anything but a public account nkey (such as a
+    /// seed or a JWT passed by mistake).
+    pub fn set_system_account(&mut self, account_public: impl Into<String>) -> anyhow::Result<()> {
+        let account_public = account_public.into();
+        if !is_valid_public_key(&account_public, KeyPairType::Account) {
+            return Err(anyhow::anyhow!(
+                "system account {account_public} is not a valid account public key"
+            ));
+        }
+        self.system_account = Some(account_public);
+        Ok(())
+    }
+
+    pub fn with_account_server_url(mut self, url: impl Into<String>) -> anyhow::Result<Self> {
+        let url = url.into();
+        check_url(&url, "account server", ACCOUNT_SERVER_URL_SCHEMES)?;
+        self.account_server_url = Some(url);
+        Ok(self)
+    }
+
+    /// Adds an operator service url, ignoring urls that are already present.
+    pub fn add_service_url(&mut self, url: impl Into<String>) -> anyhow::Result<()> {
+        let url = url.into();
+        check_url(&url, "operator service", SERVICE_URL_SCHEMES)?;
+        let urls = self.operator_service_urls.get_or_insert_with(Vec::new);
+        if !urls.contains(&url) {
+            urls.push(url);
+        }
+        Ok(())
+    }
+
+    /// Returns the URL to fetch account JWTs from: the account server if set, otherwise the
+    /// first operator service URL.
+    pub fn resolver_url(&self) -> Option<&str> {
+        self.all_resolver_urls().into_iter().next()
+    }
+
+    /// Returns the account server URL followed by the operator service URLs, in order of
+    /// preference.
+    pub fn all_resolver_urls(&self) -> Vec<&str> {
+        self.account_server_url
+            .iter()
+            .chain(self.operator_service_urls.iter().flatten())
+            .map(String::as_str)
+            .collect()
+    }
+
+    /// Compares a running server's version against `assert_server_version`, ignoring any
+    /// pre-release suffix like nats-server does.
+    ///
+    /// Returns `None` if no minimum is asserted or either version cannot be parsed.
+    pub fn server_version_satisfied(&self, actual: &str) -> Option<bool> {
+        let minimum = parse_server_version(self.assert_server_version.as_deref()?)?;
+        let actual = parse_server_version(actual)?;
+        Some(actual >= minimum)
+    }
+
+    /// Signs `account` with `operator_key`, which must be either the identity key of this
+    /// operator, identified by `operator_public`, or one of its signing keys. With
+    /// `strict_signing_key_usage` set it must be a signing key.
+    pub fn issue_account(
+        &self,
+        operator_public: &str,
+        operator_key: &KeyPair,
+        account: &Claims<Account>,
+    ) -> anyhow::Result<String> {
+        if !is_valid_public_key(&account.sub, KeyPairType::Account) {
+            return Err(anyhow::anyhow!(
+                "account subject {} is not a valid account public key",
+                account.sub
+            ));
+        }
+        let signer = operator_key.public_key();
+        if !is_valid_public_key(&signer, KeyPairType::Operator) {
+            return Err(anyhow::anyhow!("{signer} is not an operator key"));
+        }
+        if signer != operator_public && !self.has_signing_key(&signer) {
+            return Err(anyhow::anyhow!(
+                "{signer} is neither operator {operator_public} nor one of its signing keys"
+            ));
+        }
+        if self.strict_signing_key_usage == Some(true) && !self.has_signing_key(&signer) {
+            return Err(anyhow::anyhow!(
+                "operator requires strict signing key usage, but {signer} is not a signing key"
+            ));
+        }
+
+        Ok(account.encode(operator_key)?)
+    }
+
+    /// Returns `true` if `account` was issued by this operator, identified by `operator_public`,
+    /// or by one of its signing keys.
+    pub fn validates_account(&self, operator_public: &str, account: &Claims<Account>) -> bool {
+        account.iss == operator_public || self.has_signing_key(&account.iss)
+    }
+}
+
+/// Parses a `major.minor.patch` server version, accepting an optional leading `v` and
+/// discarding any `-prerelease` or `+build` suffix.
+fn parse_server_version(version: &str) -> Option<(u64, u64, u64)> {
+    let version = version.strip_prefix('v').unwrap_or(version);
+    let version = version.split(['-', '+']).next()?;
+    let mut parts = version.split('.').map(|part| part.parse::<u64>().ok());
+    match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some(Some(major)), Some(Some(minor)), Some(Some(patch)), None) => {
+            Some((major, minor, patch))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use nkeys::KeyPair;
+
+    #[test]
+    fn test_add_signing_key() {
+        let key = KeyPair::new_operator().public_key();
+        let mut operator = Operator::default();
+        operator.add_signing_key(&key).unwrap();
+        operator.add_signing_key(&key).unwrap();
+
+        assert!(operator.has_signing_key(&key));
+        assert_eq!(operator.signing_keys, Some(vec![key]));
+    }
+
+    #[test]
+    fn test_add_invalid_signing_key() {
+        let mut operator = Operator::default();
+        assert!(operator
+            .add_signing_key(KeyPair::new_account().public_key())
+            .is_err());
+        assert!(operator.add_signing_key("not a key").is_err());
+        assert!(operator.signing_keys.is_none());
+    }
+
+    #[test]
+    fn test_self_sign() {
+        let operator_key = KeyPair::new_operator();
+        let mut claims = Operator::new_claims("test".to_string(), String::new());
+        let token = claims.self_sign(&operator_key).unwrap();
+
+        let decoded = Claims::<Operator>::decode(&token).unwrap();
+        assert_eq!(decoded.iss, decoded.sub);
+        assert_eq!(decoded.sub, operator_key.public_key());
+
+        assert!(claims.self_sign(&KeyPair::new_account()).is_err());
+    }
+
+    #[test]
+    fn test_validates_account() {
+        let operator_key = KeyPair::new_operator();
+        let signing_key = KeyPair::new_operator();
+        let mut operator = Operator::default();
+        operator.add_signing_key(signing_key.public_key()).unwrap();
+
+        let account = Account::new_claims("test".to_string(), KeyPair::new_account().public_key());
+        let issued =
+            |key: &KeyPair| Claims::<Account>::decode(&account.encode(key).unwrap()).unwrap();
+
+        let operator_public = operator_key.public_key();
+        assert!(operator.validates_account(&operator_public, &issued(&operator_key)));
+        assert!(operator.validates_account(&operator_public, &issued(&signing_key)));
+        assert!(!operator.validates_account(&operator_public, &issued(&KeyPair::new_operator())));
+    }
+
+    #[test]
+    fn test_set_system_account() {
+        let account = KeyPair::new_account().public_key();
+        let mut operator = Operator::default();
+        operator.set_system_account(&account).unwrap();
+        assert_eq!(operator.system_account, Some(account));
+    }
+
+    #[test]
+    fn test_set_system_account_rejects_user_key() {
+        let mut operator = Operator::default();
+        assert!(operator
+            .set_system_account(KeyPair::new_user().public_key())
+            .is_err());
+        assert!(operator
+            .set_system_account(KeyPair::new_account().seed().unwrap())
+            .is_err());
+        assert!(operator.system_account.is_none());
+    }
+
+    #[test]
+    fn test_urls() {
+        let mut operator = Operator::default()
+            .with_account_server_url("https://resolver.example.com/jwt/v1")
+            .unwrap();
+        operator.add_service_url("nats://localhost:4222").unwrap();
+        operator.add_service_url("nats://localhost:4222").unwrap();
+        assert!(operator.add_service_url("not a url").is_err());
+        assert!(Operator::default()
+            .with_account_server_url("localhost")
+            .is_err());
+
+        assert_eq!(
+            operator.operator_service_urls,
+            Some(vec!["nats://localhost:4222".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_resolver_urls() {
+        let mut operator = Operator::default()
+            .with_account_server_url("https://resolver.example.com/jwt/v1")
+            .unwrap();
+        operator
+            .add_service_url("nats://a.example.com:4222")
+            .unwrap();
+        operator
+            .add_service_url("nats://b.example.com:4222")
+            .unwrap();
+        assert_eq!(
+            operator.resolver_url(),
+            Some("https://resolver.example.com/jwt/v1")
+        );
+        assert_eq!(
+            operator.all_resolver_urls(),
+            [
+                "https://resolver.example.com/jwt/v1",
+                "nats://a.example.com:4222",
+                "nats://b.example.com:4222"
+            ]
+        );
+
+        operator.account_server_url = None;
+        assert_eq!(operator.resolver_url(), Some("nats://a.example.com:4222"));
+        assert_eq!(operator.all_resolver_urls().len(), 2);
+        assert_eq!(Operator::default().resolver_url(), None);
+    }
+
+    #[test]
+    fn test_display_summary() {
+        let key = KeyPair::new_operator();
+        let mut claims = Operator::new_claims("acme".to_string(), key.public_key());
+        claims
+            .nats
+            .add_signing_key(KeyPair::new_operator().public_key())
+            .unwrap();
+        let summary = claims.to_string();
+        assert!(summary.contains("acme"));
+        assert!(summary.contains(&key.public_key()));
+        assert!(summary.contains("Signing keys:   1"));
+    }
+
+    #[test]
+    fn test_server_version_satisfied() {
+        let mut operator = Operator::default();
+        assert_eq!(operator.server_version_satisfied("2.10.0"), None);
+
+        operator.assert_server_version = Some("2.10.0".to_string());
+        assert_eq!(operator.server_version_satisfied("2.11.0"), Some(true));
+        assert_eq!(operator.server_version_satisfied("2.10.0"), Some(true));
+        assert_eq!(
+            operator.server_version_satisfied("v2.10.18-beta.1"),
+            Some(true)
+        );
+        assert_eq!(operator.server_version_satisfied("2.9.0"), Some(false));
+        assert_eq!(operator.server_version_satisfied("2.9"), None);
+    }
+
+    #[test]
+    fn test_issue_account() {
+        let operator_key = KeyPair::new_operator();
+        let operator = Operator::new_claims("test".to_string(), operator_key.public_key());
+        let account =
+            Account::new_claims("account".to_string(), KeyPair::new_account().public_key());
+
+        let jwt = operator
+            .nats
+            .issue_account(&operator.sub, &operator_key, &account)
+            .unwrap();
+        let decoded = Claims::<Account>::decode(&jwt).unwrap();
+        assert_eq!(decoded.iss, operator_key.public_key());
+        assert_eq!(decoded.sub, account.sub);
+
+        let invalid = Account::new_claims("account".to_string(), KeyPair::new_user().public_key());
+        assert!(operator
+            .nats
+            .issue_account(&operator.sub, &operator_key, &invalid)
+            .is_err());
+        assert!(operator
+            .nats
+            .issue_account(&operator.sub, &KeyPair::new_account(), &account)
+            .is_err());
+        // an operator key that belongs to a different operator
+        assert!(operator
+            .nats
+            .issue_account(&operator.sub, &KeyPair::new_operator(), &account)
+            .is_err());
+    }
+
+    #[test]
+    fn test_issue_account_strict_signing_keys() {
+        let operator_key = KeyPair::new_operator();
+        let signing_key = KeyPair::new_operator();
+        let mut operator = Operator::new_claims("test".to_string(), operator_key.public_key());
+        operator.nats.strict_signing_key_usage = Some(true);
+        operator
+            .nats
+            .add_signing_key(signing_key.public_key())
+            .unwrap();
+        let account =
+            Account::new_claims("account".to_string(), KeyPair::new_account().public_key());
+
+        assert!(operator
+            .nats
+            .issue_account(&operator.sub, &operator_key, &account)
+            .is_err());
+        let jwt = operator
+            .nats
+            .issue_account(&operator.sub, &signing_key, &account)
+            .unwrap();
+        assert_eq!(
+            Claims::<Account>::decode(&jwt).unwrap().iss,
+            signing_key.public_key()
+        );
+    }
+
+    #[test]
+    fn test_validate_issuer() {
+        let key = KeyPair::new_operator();
+        let operator = Operator::new_claims("test".to_string(), key.public_key());
+        let signed = Claims::<Operator>::decode(&operator.encode(&key).unwrap()).unwrap();
+        let mut vr = ValidationResults::new();
+        signed.validate(&mut vr);
+        assert!(vr.is_empty());
+
+        let signed =
+            Claims::<Operator>::decode(&operator.encode(&KeyPair::new_operator()).unwrap())
+                .unwrap();
+        let mut vr = ValidationResults::new();
+        signed.validate(&mut vr);
+        assert!(!vr.is_empty());
+        assert!(!vr.is_blocking(true));
+    }
+
+    #[test]
+    fn test_validate_server_version() {
+        let mut claims =
+            Operator::new_claims("test".to_string(), KeyPair::new_operator().public_key());
+        claims.nats.assert_server_version = Some("2.10.0".to_string());
+        let mut vr = ValidationResults::new();
+        claims.validate(&mut vr);
+        assert!(vr.is_empty());
+
+        claims.nats.assert_server_version = Some("latest".to_string());
+        let mut vr = ValidationResults::new();
+        claims.validate(&mut vr);
+        assert!(vr.is_blocking(true));
+    }
+
+    #[test]
+    fn test_validate_url_schemes() {
+        let mut claims =
+            Operator::new_claims("test".to_string(), KeyPair::new_operator().public_key());
+        claims.nats.operator_service_urls = Some(vec!["nats://localhost:4222".to_string()]);
+        claims.nats.account_server_url = Some("https://resolver.example.com/jwt/v1".to_string());
+        let mut vr = ValidationResults::new();
+        claims.validate(&mut vr);
+        assert!(vr.is_empty());
+
+        claims.nats.operator_service_urls = Some(vec!["http://localhost:4222".to_string()]);
+        let mut vr = ValidationResults::new();
+        claims.validate(&mut vr);
+        assert!(vr.is_blocking(true));
+
+        assert!(Operator::default()
+            .add_service_url("http://localhost:4222")
+            .is_err());
+        assert!(Operator::default()
+            .with_account_server_url("tls://resolver.example.com")
+            .is_err());
+    }
+
+    #[test]
+    fn test_remove_signing_key() {
+        let first = KeyPair::new_operator().public_key();
+        let second = KeyPair::new_operator().public_key();
+        let mut operator = Operator::default();
+        operator.add_signing_key(&first).unwrap();
+        operator.add_signing_key(&second).unwrap();
+
+        assert!(operator.remove_signing_key(&first));
+        assert!(!operator.remove_signing_key(&first));
+        assert!(!operator.has_signing_key(&first));
+        assert!(operator.has_signing_key(&second));
+
+        assert!(operator.remove_signing_key(&second));
+        assert!(operator.signing_keys.is_none());
+    }
 }
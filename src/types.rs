@@ -1,13 +1,21 @@
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize};
 use std::collections::BTreeMap;
 use std::fmt::Display;
+use std::str::FromStr;
 use std::time::Duration;
 
-use crate::{user::UserPermissionLimits, ClaimType};
+use crate::{
+    activation::Activation,
+    user::UserPermissionLimits,
+    validation::{is_valid_public_key, ValidationResults},
+    Claim, ClaimType, Claims,
+};
+use nkeys::KeyPairType;
 
 pub const NO_LIMIT: i64 = -1;
 
 #[derive(Debug, Serialize, Deserialize, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct NatsLimits {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub subs: Option<i64>,
@@ -27,7 +35,40 @@ impl Default for NatsLimits {
     }
 }
 
+impl NatsLimits {
+    /// Returns `true` if none of these limits exceed those of `other`, treating [`NO_LIMIT`] as
+    /// unbounded. An absent limit is read as `0`, as nats.go does.
+    pub fn is_within(&self, other: &NatsLimits) -> bool {
+        let within =
+            |limit: Option<i64>, bound: Option<i64>| match (limit.unwrap_or(0), bound.unwrap_or(0))
+            {
+                (_, NO_LIMIT) => true,
+                (NO_LIMIT, _) => false,
+                (limit, bound) => limit <= bound,
+            };
+        within(self.subs, other.subs)
+            && within(self.data, other.data)
+            && within(self.payload, other.payload)
+    }
+
+    pub fn validate(&self, vr: &mut ValidationResults) {
+        for (kind, limit) in [
+            ("subscription", self.subs),
+            ("data", self.data),
+            ("payload", self.payload),
+        ] {
+            if let Some(limit) = limit.filter(|l| *l < 0 && *l != NO_LIMIT) {
+                vr.add_error(format!(
+                    "{kind} limit {limit} must be non-negative or {NO_LIMIT}"
+                ));
+            }
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(from = "LimitsFields")]
 pub struct Limits {
     #[serde(flatten, skip_serializing_if = "Option::is_none")]
     pub user_limits: Option<UserLimits>,
@@ -35,34 +76,65 @@ pub struct Limits {
     pub nats_limits: Option<NatsLimits>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Hash, PartialEq, Eq)]
+/// The wire form of [`Limits`]. A flattened [`UserLimits`] always deserializes since all of its
+/// fields are optional, so an empty one is read back as `None`.
+#[derive(Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+struct LimitsFields {
+    #[serde(flatten)]
+    user_limits: Option<UserLimits>,
+    #[serde(flatten)]
+    nats_limits: Option<NatsLimits>,
+}
+
+impl From<LimitsFields> for Limits {
+    fn from(fields: LimitsFields) -> Self {
+        Self {
+            user_limits: fields
+                .user_limits
+                .filter(|limits| *limits != UserLimits::default()),
+            nats_limits: fields.nats_limits,
+        }
+    }
+}
+
+// every field is optional, as in nats.go, so that flattening into `Limits` does not drop a
+// partially set `UserLimits`
+#[derive(Debug, Serialize, Deserialize, Clone, Default, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(default)]
 pub struct UserLimits {
     // TODO need to parse as an array of strings or a comma separated list, which means we need a
     // custom deserializer or just use a comma separated list since that's what nats actually uses
+    #[serde(skip_serializing_if = "Vec::is_empty")]
     src: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
     times: Vec<TimeRange>,
+    #[serde(rename = "times_location", skip_serializing_if = "String::is_empty")]
     locale: String,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct TimeRange {
     start: String,
     end: String,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Import {
-    #[serde(skip_serializing_if = "String::is_empty")]
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub name: String,
-    #[serde(skip_serializing_if = "String::is_empty")]
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub subject: String,
-    #[serde(skip_serializing_if = "String::is_empty")]
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub account: String,
-    #[serde(skip_serializing_if = "String::is_empty")]
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub token: String,
-    #[serde(skip_serializing_if = "String::is_empty")]
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub to: String,
-    #[serde(skip_serializing_if = "String::is_empty")]
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub local_subject: String,
     #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
     pub export_type: Option<ExportType>,
@@ -70,10 +142,15 @@ pub struct Import {
     pub share: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub allow_trace: Option<bool>,
+    /// A local note for tooling. nats-server does not carry descriptions on imports, so this is
+    /// never serialized.
+    #[serde(skip)]
+    pub description: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
-#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(default, rename_all = "snake_case")]
 pub struct Export {
     #[serde(skip_serializing_if = "String::is_empty")]
     pub name: String,
@@ -83,13 +160,18 @@ pub struct Export {
     pub export_type: Option<ExportType>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub token_req: Option<bool>,
-    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
-    pub revocations: BTreeMap<String, u64>,
+    #[serde(skip_serializing_if = "RevocationList::is_empty")]
+    pub revocations: RevocationList,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub response_type: Option<ResponseType>,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(
+        default,
+        with = "go_duration_format::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[cfg_attr(feature = "schemars", schemars(with = "Option<u64>"))]
     pub response_threshold: Option<Duration>,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "service_latency", skip_serializing_if = "Option::is_none")]
     pub latency: Option<ServiceLatency>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub account_token_position: Option<u64>,
@@ -101,13 +183,249 @@ pub struct Export {
     pub info: Option<Info>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+/// Revoked public keys mapped to the unix time of their revocation.
+///
+/// Any credential for a key issued at or before its revocation time is revoked. The special key
+/// `*` revokes every credential issued at or before its time.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(from = "BTreeMap<String, u64>", into = "BTreeMap<String, u64>")]
+pub struct RevocationList(BTreeMap<String, u64>);
+
+impl RevocationList {
+    /// The key that revokes all credentials.
+    pub const ALL: &'static str = "*";
+
+    /// Revokes credentials for `key` issued at or before `at`.
+    pub fn revoke(&mut self, key: impl Into<String>, at: u64) {
+        self.0.insert(key.into(), at);
+    }
+
+    /// Revokes every credential issued at or before `at`.
+    pub fn revoke_all(&mut self, at: u64) {
+        self.revoke(Self::ALL, at);
+    }
+
+    /// Removes the revocation for `key`, returning its time if one was present.
+    pub fn clear(&mut self, key: &str) -> Option<u64> {
+        self.0.remove(key)
+    }
+
+    /// Returns `true` if a credential for `key` issued at `issued_at` is revoked, either
+    /// specifically or by the `*` wildcard.
+    pub fn is_revoked(&self, key: &str, issued_at: u64) -> bool {
+        [key, Self::ALL]
+            .iter()
+            .any(|k| self.0.get(*k).is_some_and(|at| issued_at <= *at))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &u64)> {
+        self.0.iter()
+    }
+}
+
+impl From<BTreeMap<String, u64>> for RevocationList {
+    fn from(revocations: BTreeMap<String, u64>) -> Self {
+        Self(revocations)
+    }
+}
+
+impl From<RevocationList> for BTreeMap<String, u64> {
+    fn from(revocations: RevocationList) -> Self {
+        revocations.0
+    }
+}
+
+impl Import {
+    /// Stores the activation token granting access to a token-required export, after checking
+    /// that it is a correctly signed activation.
+    pub fn set_activation(&mut self, activation_jwt: &str) -> anyhow::Result<()> {
+        Claims::<Activation>::decode_checked(activation_jwt)?;
+        self.token = activation_jwt.to_string();
+        Ok(())
+    }
+
+    /// Decodes and verifies the embedded activation token, if any.
+    pub fn decode_activation(&self) -> anyhow::Result<Option<Claims<Activation>>> {
+        if self.token.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(Claims::<Activation>::decode_checked(&self.token)?))
+    }
+
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    pub fn set_description(&mut self, description: impl Into<String>) {
+        self.description = Some(description.into());
+    }
+
+    pub fn validate(&self, vr: &mut ValidationResults) {
+        if self.export_type == Some(ExportType::Unknown) {
+            vr.add_error(format!("import {} has an unknown type", self.subject));
+        }
+
+        if !self.to.is_empty() && !self.local_subject.is_empty() {
+            vr.add_error(format!(
+                "import {} sets both to and local_subject, which conflict",
+                self.subject
+            ));
+        }
+        if !self.to.is_empty() {
+            vr.add_warning(format!(
+                "import {} uses the deprecated to field, use local_subject instead",
+                self.subject
+            ));
+        }
+        for (field, subject) in [("to", &self.to), ("local_subject", &self.local_subject)] {
+            if !subject.is_empty() && !is_valid_subject(subject) {
+                vr.add_error(format!(
+                    "import {} has an invalid {field} subject {subject}",
+                    self.subject
+                ));
+            }
+        }
+    }
+}
+
+impl Export {
+    pub fn is_service(&self) -> bool {
+        self.export_type == Some(ExportType::Service)
+    }
+
+    pub fn is_stream(&self) -> bool {
+        self.export_type == Some(ExportType::Stream)
+    }
+
+    /// Sets `account_token_position` to the 1-based index of the first `*` token in the
+    /// subject, the token importers fill in with their account public key.
+    pub fn derive_token_position(&mut self) -> anyhow::Result<()> {
+        let position = self
+            .subject
+            .split('.')
+            .position(|token| token == "*")
+            .ok_or_else(|| {
+                anyhow::anyhow!("export subject {} has no * wildcard token", self.subject)
+            })?;
+        self.account_token_position = Some(position as u64 + 1);
+        Ok(())
+    }
+
+    /// Revokes activations for the importing account issued at or before `at`. Pass
+    /// [`RevocationList::ALL`] to revoke every importer.
+    pub fn revoke(&mut self, account_public: impl Into<String>, at: u64) -> anyhow::Result<()> {
+        let account_public = account_public.into();
+        if account_public != RevocationList::ALL
+            && !is_valid_public_key(&account_public, KeyPairType::Account)
+        {
+            return Err(anyhow::anyhow!(
+                "{account_public} is not a valid account public key"
+            ));
+        }
+        self.revocations.revoke(account_public, at);
+        Ok(())
+    }
+
+    /// Returns `true` if an activation for `account_public` issued at `activated_at` has been
+    /// revoked, either specifically or for all importers.
+    pub fn is_revoked(&self, account_public: &str, activated_at: u64) -> bool {
+        self.revocations.is_revoked(account_public, activated_at)
+    }
+
+    /// Singleton responses complete with the first reply, so a threshold on them has no effect,
+    /// while a zero threshold on multi-message responses ends them immediately.
+    fn validate_response_threshold(&self, vr: &mut ValidationResults) {
+        let Some(threshold) = self.response_threshold else {
+            return;
+        };
+        match self.response_type {
+            Some(ResponseType::Singleton) => vr.add_warning(format!(
+                "service export {} has a response threshold, which singleton responses ignore",
+                self.subject
+            )),
+            Some(ResponseType::Stream | ResponseType::Chunked) if threshold.is_zero() => vr
+                .add_warning(format!(
+                    "service export {} has a zero response threshold for a multi-message response",
+                    self.subject
+                )),
+            _ => {}
+        }
+    }
+
+    pub fn validate(&self, vr: &mut ValidationResults) {
+        if self.export_type == Some(ExportType::Unknown) {
+            vr.add_error(format!("export {} has an unknown type", self.subject));
+        }
+
+        if self.is_stream() {
+            // these only apply to the request/reply flow of services
+            if self.latency.is_some() {
+                vr.add_error(format!(
+                    "stream export {} has service latency tracking, which only applies to services",
+                    self.subject
+                ));
+            }
+            if self.response_type.is_some() {
+                vr.add_warning(format!(
+                    "stream export {} has a response type, which only applies to services",
+                    self.subject
+                ));
+            }
+            if self.response_threshold.is_some() {
+                vr.add_warning(format!(
+                    "stream export {} has a response threshold, which only applies to services",
+                    self.subject
+                ));
+            }
+        } else if self.is_service() {
+            if let Some(latency) = &self.latency {
+                if !is_valid_subject(&latency.results) || subject_has_wildcards(&latency.results) {
+                    vr.add_error(format!(
+                        "service export {} has latency results subject {}, which must be a valid subject without wildcards",
+                        self.subject, latency.results
+                    ));
+                }
+            }
+            self.validate_response_threshold(vr);
+        }
+
+        if let Some(position) = self.account_token_position {
+            let tokens: Vec<&str> = self.subject.split('.').collect();
+            match usize::try_from(position)
+                .ok()
+                .filter(|p| *p >= 1)
+                .and_then(|p| tokens.get(p - 1))
+            {
+                None => vr.add_error(format!(
+                    "account token position {position} is outside the {} tokens of subject {}",
+                    tokens.len(),
+                    self.subject
+                )),
+                Some(&"*") => {}
+                Some(token) => vr.add_error(format!(
+                    "account token position {position} of subject {} references {token}, not a * wildcard",
+                    self.subject
+                )),
+            }
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[serde(rename_all = "lowercase")]
 pub enum ExportType {
-    Unknown,
     #[default]
     Stream,
     Service,
+    /// Any unrecognized type string, reported as a blocking issue during validation.
+    #[serde(other)]
+    Unknown,
 }
 
 impl Display for ExportType {
@@ -120,12 +438,90 @@ impl Display for ExportType {
     }
 }
 
+/// Returns `true` if `import` resolves against `export`: the types agree, the imported subject
+/// falls within the exported subject, and an activation token is present when one is required.
+pub fn import_matches_export(import: &Import, export: &Export) -> bool {
+    let import_type = import.export_type.clone().unwrap_or_default();
+    let export_type = export.export_type.clone().unwrap_or_default();
+    if import_type != export_type {
+        return false;
+    }
+
+    if export.token_req == Some(true) && import.token.is_empty() {
+        return false;
+    }
+
+    subject_is_subset(&import.subject, &export.subject)
+}
+
+/// Returns `true` if `subject` is a well-formed NATS subject: non-empty tokens separated by `.`,
+/// without whitespace, where `*` and `>` only appear as whole tokens and `>` only as the last.
+pub fn is_valid_subject(subject: &str) -> bool {
+    if subject.is_empty() || subject.contains(char::is_whitespace) {
+        return false;
+    }
+
+    let tokens: Vec<&str> = subject.split('.').collect();
+    tokens.iter().enumerate().all(|(i, token)| match *token {
+        "" => false,
+        ">" => i == tokens.len() - 1,
+        "*" => true,
+        token => !token.contains(['*', '>']),
+    })
+}
+
+/// Returns `true` if `subject` contains a `*` or `>` wildcard token.
+pub fn subject_has_wildcards(subject: &str) -> bool {
+    subject.split('.').any(|token| token == "*" || token == ">")
+}
+
+/// Returns `true` if every subject matched by `subject` is also matched by `pattern`.
+///
+/// Matching is token by token: `*` matches exactly one token and `>` matches one or more
+/// trailing tokens. `subject` may itself contain wildcards, in which case it is only contained
+/// when `pattern` is at least as broad at the same positions.
+pub fn subject_is_subset(subject: &str, pattern: &str) -> bool {
+    let pattern: Vec<&str> = pattern.split('.').collect();
+    let subject: Vec<&str> = subject.split('.').collect();
+
+    for (i, p) in pattern.iter().enumerate() {
+        match (*p, subject.get(i)) {
+            (">", Some(_)) => return true,
+            (_, None) => return false,
+            (_, Some(&">")) => return false,
+            ("*", Some(_)) => {}
+            (_, Some(&"*")) => return false,
+            (p, Some(s)) if p != *s => return false,
+            _ => {}
+        }
+    }
+
+    pattern.len() == subject.len()
+}
+
+/// Returns `true` if some concrete subject is matched by both `a` and `b`.
+fn subjects_overlap(a: &str, b: &str) -> bool {
+    let a: Vec<&str> = a.split('.').collect();
+    let b: Vec<&str> = b.split('.').collect();
+    for i in 0..a.len().max(b.len()) {
+        match (a.get(i), b.get(i)) {
+            (Some(&">"), Some(_)) | (Some(_), Some(&">")) => return true,
+            (Some(&"*"), Some(_)) | (Some(_), Some(&"*")) => {}
+            (Some(x), Some(y)) if x == y => {}
+            _ => return false,
+        }
+    }
+    true
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct ServiceLatency {
     results: String,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub struct Info {
     #[serde(skip_serializing_if = "String::is_empty")]
@@ -134,7 +530,8 @@ pub struct Info {
     info_url: String,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub enum ResponseType {
     Singleton,
     Stream,
@@ -151,7 +548,30 @@ impl Display for ResponseType {
     }
 }
 
+impl FromStr for ResponseType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "singleton" => Ok(ResponseType::Singleton),
+            "stream" => Ok(ResponseType::Stream),
+            "chunked" => Ok(ResponseType::Chunked),
+            _ => Err(anyhow::anyhow!("unknown response type: {s}")),
+        }
+    }
+}
+
+/// Accepts any casing, since some tools emit lowercase response types. Serialization always
+/// uses the capitalized form.
+impl<'de> Deserialize<'de> for ResponseType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct GenericFields {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tags: Option<Vec<String>>,
@@ -170,7 +590,69 @@ impl Default for GenericFields {
     }
 }
 
+impl GenericFields {
+    /// Adds a tag, lowercased like nats.go, unless it is already present.
+    pub fn add_tag(&mut self, tag: impl Into<String>) {
+        let tag = tag.into().to_lowercase();
+        let tags = self.tags.get_or_insert_with(Vec::new);
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+
+    /// Removes a tag, comparing case-insensitively. Returns `true` if it was present.
+    pub fn remove_tag(&mut self, tag: &str) -> bool {
+        let Some(tags) = self.tags.as_mut() else {
+            return false;
+        };
+        let tag = tag.to_lowercase();
+        let len = tags.len();
+        tags.retain(|t| t.to_lowercase() != tag);
+        let removed = tags.len() != len;
+        if tags.is_empty() {
+            self.tags = None;
+        }
+        removed
+    }
+
+    /// Lowercases and de-duplicates existing tags, preserving their first-seen order.
+    pub fn normalize_tags(&mut self) {
+        if let Some(tags) = self.tags.take() {
+            for tag in tags {
+                self.add_tag(tag);
+            }
+        }
+    }
+}
+
+/// An untyped `nats` payload that keeps every field of a decoded token, including ones this
+/// crate does not model, so a decode-edit-encode cycle does not drop them.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(transparent)]
+pub struct RawNats(pub serde_json::Map<String, serde_json::Value>);
+
+impl RawNats {
+    /// Deserializes the payload into a typed view such as [`User`](crate::user::User).
+    pub fn typed<T: DeserializeOwned>(&self) -> serde_json::Result<T> {
+        T::deserialize(serde_json::Value::Object(self.0.clone()))
+    }
+}
+
+impl Claim for RawNats {
+    const CLAIM_TYPE: ClaimType = ClaimType::Generic;
+
+    fn validate(_claims: &Claims<Self>, _vr: &mut ValidationResults) {}
+}
+
+impl Claims<RawNats> {
+    /// Sets a field of the `nats` payload, replacing any previous value.
+    pub fn set_field(&mut self, key: impl Into<String>, value: impl Into<serde_json::Value>) {
+        self.nats.0.insert(key.into(), value.into());
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, Default, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Permissions {
     #[serde(default, rename = "pub")]
     pub publish: Permission,
@@ -180,7 +662,57 @@ pub struct Permissions {
     pub resp: Option<ResponsePermission>,
 }
 
+impl Permissions {
+    /// Denies publishing and subscribing to every subject.
+    pub fn deny_all() -> Self {
+        Self {
+            publish: Permission::deny_all(),
+            subscribe: Permission::deny_all(),
+            resp: None,
+        }
+    }
+
+    /// Allows publishing and subscribing to every subject.
+    pub fn allow_all() -> Self {
+        Self {
+            publish: Permission::allow_all(),
+            subscribe: Permission::allow_all(),
+            resp: None,
+        }
+    }
+
+    /// Checks that every permission subject is valid. Subscribe entries may name a queue group
+    /// after a space, as in `orders.* workers`.
+    pub fn validate(&self, vr: &mut ValidationResults) {
+        for subject in self.publish.allow.iter().chain(&self.publish.deny) {
+            if !is_valid_subject(subject) {
+                vr.add_error(format!(
+                    "publish permission {subject} is not a valid subject"
+                ));
+            }
+        }
+        for entry in self.subscribe.allow.iter().chain(&self.subscribe.deny) {
+            let subject = match entry.split_once(' ') {
+                Some((subject, queue)) if !queue.is_empty() && !queue.contains(' ') => subject,
+                _ => entry.as_str(),
+            };
+            if !is_valid_subject(subject) {
+                vr.add_error(format!(
+                    "subscribe permission {entry} is not a valid subject"
+                ));
+            }
+        }
+    }
+
+    /// Normalizes the publish and subscribe permissions, see [`Permission::normalize`].
+    pub fn normalize(&mut self) {
+        self.publish.normalize();
+        self.subscribe.normalize();
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Default, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Permission {
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub allow: Vec<String>,
@@ -188,15 +720,56 @@ pub struct Permission {
     pub deny: Vec<String>,
 }
 
+impl Permission {
+    /// Denies every subject with a `>` wildcard.
+    pub fn deny_all() -> Self {
+        Self {
+            allow: Vec::new(),
+            deny: vec![">".to_string()],
+        }
+    }
+
+    /// Allows every subject with a `>` wildcard.
+    pub fn allow_all() -> Self {
+        Self {
+            allow: vec![">".to_string()],
+            deny: Vec::new(),
+        }
+    }
+
+    /// Sorts and de-duplicates the allow and deny lists, as nsc stores them.
+    pub fn normalize(&mut self) {
+        for subjects in [&mut self.allow, &mut self.deny] {
+            subjects.sort_unstable();
+            subjects.dedup();
+        }
+    }
+
+    /// Returns `true` if every subject matched by `subject` is permitted: an empty allow list
+    /// permits everything, and a deny entry blocks `subject` if it covers any part of it.
+    pub fn allows(&self, subject: &str) -> bool {
+        let allowed =
+            self.allow.is_empty() || self.allow.iter().any(|a| subject_is_subset(subject, a));
+        allowed && !self.deny.iter().any(|d| subjects_overlap(subject, d))
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, Default, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct ResponsePermission {
     #[serde(rename = "max")]
     pub max_messages: i64,
-    #[serde(with = "go_duration_format", skip_serializing_if = "Duration::is_zero")]
+    #[serde(
+        default,
+        with = "go_duration_format",
+        skip_serializing_if = "Duration::is_zero"
+    )]
+    #[cfg_attr(feature = "schemars", schemars(with = "u64"))]
     pub ttl: Duration,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Default, Hash, Eq, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum ScopeType {
     #[default]
@@ -204,6 +777,7 @@ pub enum ScopeType {
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Default, Hash, Eq, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct UserScope {
     pub kind: ScopeType,
     pub key: String,
@@ -216,6 +790,7 @@ pub struct UserScope {
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Hash, Eq, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[serde(from = "KeyOrScope", into = "KeyOrScope")]
 pub struct SigningKey {
     pub key: String,
@@ -248,6 +823,7 @@ impl From<SigningKey> for KeyOrScope {
 }
 
 #[derive(Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[serde(untagged)]
 #[allow(clippy::large_enum_variant)]
 enum KeyOrScope {
@@ -255,61 +831,664 @@ enum KeyOrScope {
     Scope(UserScope),
 }
 
-#[derive(Debug, Clone)]
-enum SamplingRate {
-    Headers,
-    Percentage(u32),
-}
+mod go_duration_format {
+    use std::time::Duration;
 
-impl Serialize for SamplingRate {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    use serde::{self, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error>
     where
-        S: serde::Serializer,
+        S: Serializer,
     {
-        match self {
-            SamplingRate::Headers => serializer.serialize_str("headers"),
-            SamplingRate::Percentage(p) => serializer.serialize_u32(*p),
-        }
+        // Go durations are int64 nanoseconds; u128 is also unsupported inside flattened or
+        // untagged structs
+        let nanos = i64::try_from(duration.as_nanos()).map_err(serde::ser::Error::custom)?;
+        serializer.serialize_i64(nanos)
     }
-}
 
-impl<'de> Deserialize<'de> for SamplingRate {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Duration, D::Error>
     where
-        D: serde::Deserializer<'de>,
+        D: Deserializer<'de>,
     {
-        #[derive(Deserialize)]
-        enum Rates {
-            String(String),
-            U32(u32),
+        from_nanos(i64::deserialize(deserializer)?)
+    }
+
+    fn from_nanos<E: serde::de::Error>(nanos: i64) -> Result<Duration, E> {
+        u64::try_from(nanos)
+            .map(Duration::from_nanos)
+            .map_err(|_| E::custom(format!("duration must not be negative, found {nanos}ns")))
+    }
+
+    pub mod option {
+        use std::time::Duration;
+
+        use serde::{Deserialize, Deserializer, Serializer};
+
+        pub fn serialize<S>(duration: &Option<Duration>, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            match duration {
+                Some(duration) => super::serialize(duration, serializer),
+                None => serializer.serialize_none(),
+            }
         }
-        let data = Rates::deserialize(deserializer)?;
-        match data {
-            Rates::String(_s) => Ok(SamplingRate::Headers),
-            Rates::U32(u) => Ok(SamplingRate::Percentage(u)),
+
+        pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<Duration>, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            Option::<i64>::deserialize(deserializer)?
+                .map(super::from_nanos)
+                .transpose()
         }
     }
 }
 
-mod go_duration_format {
-    use std::time::Duration;
+#[cfg(test)]
+mod test {
+    use super::*;
 
-    use serde::{self, Deserialize, Deserializer, Serializer};
+    #[test]
+    fn test_import_matches_export() {
+        let export = Export {
+            subject: "svc.>".to_string(),
+            export_type: Some(ExportType::Service),
+            ..Default::default()
+        };
+        let import = Import {
+            subject: "svc.orders".to_string(),
+            export_type: Some(ExportType::Service),
+            ..Default::default()
+        };
+        assert!(import_matches_export(&import, &export));
 
-    pub fn serialize<S>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        serializer.serialize_u128(duration.as_nanos())
+        let stream_import = Import {
+            export_type: Some(ExportType::Stream),
+            ..import.clone()
+        };
+        assert!(!import_matches_export(&stream_import, &export));
+
+        let token_export = Export {
+            token_req: Some(true),
+            ..export.clone()
+        };
+        assert!(!import_matches_export(&import, &token_export));
     }
 
-    pub fn deserialize<'de, D>(deserializer: D) -> Result<Duration, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        let nanos =
-            u64::try_from(u128::deserialize(deserializer)?).map_err(serde::de::Error::custom)?;
-        let duration = Duration::from_nanos(nanos);
-        Ok(duration)
+    #[test]
+    fn test_import_does_not_match_export() {
+        let export = Export {
+            subject: "svc.>".to_string(),
+            export_type: Some(ExportType::Service),
+            ..Default::default()
+        };
+        let import = Import {
+            subject: "other.orders".to_string(),
+            export_type: Some(ExportType::Service),
+            ..Default::default()
+        };
+        assert!(!import_matches_export(&import, &export));
+    }
+
+    #[test]
+    fn test_is_valid_subject() {
+        for subject in [
+            "foo",
+            "foo.bar",
+            "foo.*",
+            "foo.>",
+            "*",
+            ">",
+            "_INBOX.abc-123",
+        ] {
+            assert!(is_valid_subject(subject), "{subject}");
+        }
+        for subject in [
+            "",
+            "foo.",
+            ".foo",
+            "foo..bar",
+            "foo.>.bar",
+            "foo*",
+            "fo>o",
+            "foo bar",
+        ] {
+            assert!(!is_valid_subject(subject), "{subject}");
+        }
+    }
+
+    #[test]
+    fn test_subject_has_wildcards() {
+        assert!(subject_has_wildcards("foo.*"));
+        assert!(subject_has_wildcards("foo.>"));
+        assert!(!subject_has_wildcards("foo.bar"));
+    }
+
+    #[test]
+    fn test_subject_is_subset() {
+        let cases = [
+            ("foo", "foo", true),
+            ("foo.bar", "foo.bar", true),
+            ("foo.bar", "foo.baz", false),
+            ("foo", "foo.bar", false),
+            ("foo.bar", "foo", false),
+            ("foo.bar", "foo.*", true),
+            ("foo.bar.baz", "foo.*", false),
+            ("foo", "foo.*", false),
+            ("foo.bar", "*.bar", true),
+            ("foo.bar", "*.*", true),
+            ("foo.bar", "foo.>", true),
+            ("foo.bar.baz", "foo.>", true),
+            ("foo", "foo.>", false),
+            ("foo.bar", ">", true),
+            ("foo.*", "foo.*", true),
+            ("foo.*", "foo.>", true),
+            ("foo.>", "foo.>", true),
+            ("foo.>", "foo.*", false),
+            ("foo.*", "foo.bar", false),
+            ("foo.>", "foo.bar", false),
+            ("*.bar", "foo.bar", false),
+            ("foo.*.baz", "foo.>", true),
+            ("foo.*.baz", "foo.*.*", true),
+            ("foo.*.baz", "foo.bar.*", false),
+        ];
+
+        for (subject, pattern, expected) in cases {
+            assert_eq!(
+                subject_is_subset(subject, pattern),
+                expected,
+                "{subject} subset of {pattern}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_revoke_specific() {
+        let mut revocations = RevocationList::default();
+        revocations.revoke("UA", 100);
+        assert!(revocations.is_revoked("UA", 99));
+        assert!(revocations.is_revoked("UA", 100));
+        assert!(!revocations.is_revoked("UA", 101));
+        assert!(!revocations.is_revoked("UB", 50));
+    }
+
+    #[test]
+    fn test_revoke_all() {
+        let mut revocations = RevocationList::default();
+        revocations.revoke_all(100);
+        assert!(revocations.is_revoked("UA", 100));
+        assert!(revocations.is_revoked("UB", 50));
+        assert!(!revocations.is_revoked("UB", 101));
+    }
+
+    #[test]
+    fn test_revoke_combined() {
+        let mut revocations = RevocationList::default();
+        revocations.revoke_all(100);
+        revocations.revoke("UA", 200);
+        assert!(revocations.is_revoked("UA", 150));
+        assert!(!revocations.is_revoked("UB", 150));
+        assert_eq!(revocations.clear("UA"), Some(200));
+        assert!(!revocations.is_revoked("UA", 150));
+    }
+
+    #[test]
+    fn test_revocations_wire_format() {
+        let mut export = Export::default();
+        assert!(!serde_json::to_string(&export)
+            .unwrap()
+            .contains("revocations"));
+
+        export.revocations.revoke_all(100);
+        let json = serde_json::to_value(&export).unwrap();
+        assert_eq!(json["revocations"], serde_json::json!({"*": 100}));
+
+        let export: Export = serde_json::from_value(json).unwrap();
+        assert!(export.revocations.is_revoked("UA", 100));
+    }
+
+    #[test]
+    fn test_nats_limits_is_within() {
+        let limits = |subs| NatsLimits {
+            subs: Some(subs),
+            ..Default::default()
+        };
+        assert!(limits(10).is_within(&limits(NO_LIMIT)));
+        assert!(!limits(NO_LIMIT).is_within(&limits(10)));
+        assert!(limits(10).is_within(&limits(10)));
+        assert!(!limits(11).is_within(&limits(10)));
+        assert!(NatsLimits::default().is_within(&NatsLimits::default()));
+
+        let capped = NatsLimits {
+            data: Some(1024),
+            ..Default::default()
+        };
+        assert!(!NatsLimits::default().is_within(&capped));
+    }
+
+    #[test]
+    fn test_export_revoke() {
+        let importer = nkeys::KeyPair::new_account().public_key();
+        let other = nkeys::KeyPair::new_account().public_key();
+        let mut export = Export {
+            subject: "svc.>".to_string(),
+            export_type: Some(ExportType::Service),
+            token_req: Some(true),
+            ..Default::default()
+        };
+        export.revoke(&importer, 1000).unwrap();
+
+        assert!(export.is_revoked(&importer, 999));
+        assert!(!export.is_revoked(&importer, 1001));
+        assert!(!export.is_revoked(&other, 999));
+
+        export.revoke(RevocationList::ALL, 500).unwrap();
+        assert!(export.is_revoked(&other, 499));
+    }
+
+    #[test]
+    fn test_export_revoke_rejects_non_account_key() {
+        let mut export = Export::default();
+        assert!(export
+            .revoke(nkeys::KeyPair::new_user().public_key(), 1000)
+            .is_err());
+        assert!(export.revocations.is_empty());
+    }
+
+    #[test]
+    fn test_import_description_is_not_serialized() {
+        let mut import = Import {
+            subject: "svc.orders".to_string(),
+            ..Default::default()
+        };
+        import.set_description("orders from the billing team");
+        assert_eq!(import.description(), Some("orders from the billing team"));
+
+        let json = serde_json::to_string(&import).unwrap();
+        assert!(!json.contains("description"));
+        let import: Import = serde_json::from_str(&json).unwrap();
+        assert_eq!(import.description(), None);
+    }
+
+    #[test]
+    fn test_import_local_subject() {
+        let mut import = Import {
+            subject: "svc.orders".to_string(),
+            local_subject: "orders".to_string(),
+            export_type: Some(ExportType::Service),
+            ..Default::default()
+        };
+        let mut vr = ValidationResults::new();
+        import.validate(&mut vr);
+        assert!(vr.is_empty());
+
+        import.to = "legacy.orders".to_string();
+        let mut vr = ValidationResults::new();
+        import.validate(&mut vr);
+        assert!(vr.is_blocking(false));
+
+        import.local_subject = String::new();
+        let mut vr = ValidationResults::new();
+        import.validate(&mut vr);
+        assert!(!vr.is_empty());
+        assert!(!vr.is_blocking(false));
+
+        import.to = "legacy..orders".to_string();
+        let mut vr = ValidationResults::new();
+        import.validate(&mut vr);
+        assert!(vr.is_blocking(false));
+    }
+
+    #[test]
+    fn test_import_activation() {
+        let exporter = nkeys::KeyPair::new_account();
+        let importer = nkeys::KeyPair::new_account();
+        let mut activation = Activation::new_claims("orders".to_string(), importer.public_key());
+        activation.nats.import_subject = "svc.orders".to_string();
+        activation.nats.import_type = Some(ExportType::Service);
+        let jwt = activation.encode(&exporter).unwrap();
+
+        let mut import = Import {
+            subject: "svc.orders".to_string(),
+            account: exporter.public_key(),
+            export_type: Some(ExportType::Service),
+            ..Default::default()
+        };
+        assert!(import.decode_activation().unwrap().is_none());
+        import.set_activation(&jwt).unwrap();
+        assert_eq!(import.token, jwt);
+
+        let decoded = import.decode_activation().unwrap().unwrap();
+        assert_eq!(decoded.iss, exporter.public_key());
+        assert_eq!(decoded.sub, importer.public_key());
+        assert_eq!(decoded.nats.import_subject, "svc.orders");
+    }
+
+    #[test]
+    fn test_set_activation_rejects_other_tokens() {
+        let account = nkeys::KeyPair::new_account();
+        let user = crate::user::User::new_claims(
+            "test".to_string(),
+            nkeys::KeyPair::new_user().public_key(),
+        );
+        let mut import = Import::default();
+        assert!(import
+            .set_activation(&user.encode(&account).unwrap())
+            .is_err());
+        assert!(import.set_activation("not a jwt").is_err());
+        assert!(import.token.is_empty());
+    }
+
+    #[test]
+    fn test_raw_nats_preserves_unknown_fields() {
+        let account = nkeys::KeyPair::new_account();
+        let user = crate::user::User::new_claims(
+            "test".to_string(),
+            nkeys::KeyPair::new_user().public_key(),
+        );
+        let mut json = serde_json::to_value(&user).unwrap();
+        json["nats"]["future_field"] = serde_json::json!({ "enabled": true });
+        let raw = Claims::<RawNats>::from_json(&json.to_string()).unwrap();
+        let token = raw.encode(&account).unwrap();
+
+        let mut raw = Claims::<RawNats>::decode(&token).unwrap();
+        raw.exp = Some(4102444800);
+        raw.set_field("bearer_token", true);
+        let token = raw.encode(&account).unwrap();
+
+        let raw = Claims::<RawNats>::decode(&token).unwrap();
+        assert_eq!(raw.exp, Some(4102444800));
+        assert_eq!(
+            raw.nats.0["future_field"],
+            serde_json::json!({ "enabled": true })
+        );
+        let user: crate::user::User = raw.nats.typed().unwrap();
+        assert!(user.is_bearer());
+        assert!(Claims::<crate::user::User>::decode(&token).is_ok());
+    }
+
+    #[test]
+    fn test_unknown_export_type() {
+        let export: Export =
+            serde_json::from_str(r#"{"subject":"foo.>","type":"broadcast"}"#).unwrap();
+        assert_eq!(export.export_type, Some(ExportType::Unknown));
+        let mut vr = ValidationResults::new();
+        export.validate(&mut vr);
+        assert!(vr.is_blocking(true));
+
+        let import: Import =
+            serde_json::from_str(r#"{"subject":"foo.>","type":"broadcast"}"#).unwrap();
+        let mut vr = ValidationResults::new();
+        import.validate(&mut vr);
+        assert!(vr.is_blocking(true));
+
+        let import: Import =
+            serde_json::from_str(r#"{"subject":"foo.>","type":"service"}"#).unwrap();
+        let mut vr = ValidationResults::new();
+        import.validate(&mut vr);
+        assert!(vr.is_empty());
+    }
+
+    #[test]
+    fn test_response_type_case_insensitive() {
+        for value in [r#""stream""#, r#""STREAM""#, r#""Stream""#] {
+            let response_type: ResponseType = serde_json::from_str(value).unwrap();
+            assert_eq!(response_type, ResponseType::Stream);
+        }
+        assert_eq!(
+            serde_json::to_string(&ResponseType::Stream).unwrap(),
+            r#""Stream""#
+        );
+        assert!(serde_json::from_str::<ResponseType>(r#""streaming""#).is_err());
+    }
+
+    #[test]
+    fn test_response_threshold_format() {
+        let export = Export {
+            subject: "svc.>".to_string(),
+            export_type: Some(ExportType::Service),
+            response_threshold: Some(Duration::from_secs(2)),
+            ..Default::default()
+        };
+        let json = serde_json::to_value(&export).unwrap();
+        assert_eq!(json["response_threshold"], 2_000_000_000u64);
+
+        let export: Export = serde_json::from_value(json).unwrap();
+        assert_eq!(export.response_threshold, Some(Duration::from_secs(2)));
+
+        let export: Export = serde_json::from_str(r#"{"subject": "svc.>"}"#).unwrap();
+        assert_eq!(export.response_threshold, None);
+    }
+
+    #[test]
+    fn test_response_permission_ttl_format() {
+        let resp: ResponsePermission = serde_json::from_str(r#"{"max": 1}"#).unwrap();
+        assert_eq!(resp.ttl, Duration::ZERO);
+        assert_eq!(serde_json::to_string(&resp).unwrap(), r#"{"max":1}"#);
+
+        let resp: ResponsePermission =
+            serde_json::from_str(r#"{"max": 1, "ttl": 5000000000}"#).unwrap();
+        assert_eq!(resp.ttl, Duration::from_secs(5));
+
+        // Go durations are int64 nanoseconds
+        let resp = ResponsePermission {
+            max_messages: 1,
+            ttl: Duration::from_nanos(i64::MAX as u64 + 1),
+        };
+        assert!(serde_json::to_string(&resp).is_err());
+
+        let err =
+            serde_json::from_str::<ResponsePermission>(r#"{"max": 1, "ttl": -1}"#).unwrap_err();
+        assert!(err.to_string().contains("must not be negative"));
+        let err = serde_json::from_str::<Export>(r#"{"subject": "svc", "response_threshold": -1}"#)
+            .unwrap_err();
+        assert!(err.to_string().contains("must not be negative"));
+    }
+
+    #[test]
+    fn test_stream_export_with_latency() {
+        let export = Export {
+            subject: "events.>".to_string(),
+            export_type: Some(ExportType::Stream),
+            latency: Some(ServiceLatency {
+                results: "latency.events".to_string(),
+            }),
+            ..Default::default()
+        };
+        let mut vr = ValidationResults::new();
+        export.validate(&mut vr);
+        assert!(vr.is_blocking(false));
+
+        let export = Export {
+            export_type: None,
+            ..export
+        };
+        assert!(!export.is_stream());
+        let mut vr = ValidationResults::new();
+        export.validate(&mut vr);
+        assert!(vr.is_empty());
+    }
+
+    #[test]
+    fn test_user_limits_round_trip() {
+        let json = serde_json::json!({
+            "src": ["192.168.1.0/24"],
+            "times_location": "America/New_York",
+            "subs": 10,
+            "data": -1,
+            "payload": 1024
+        });
+
+        let limits: Limits = serde_json::from_value(json.clone()).unwrap();
+        let user_limits = limits.user_limits.as_ref().unwrap();
+        assert_eq!(user_limits.src, vec!["192.168.1.0/24".to_string()]);
+        assert_eq!(user_limits.locale, "America/New_York");
+        assert_eq!(limits.nats_limits.as_ref().unwrap().subs, Some(10));
+        assert_eq!(serde_json::to_value(&limits).unwrap(), json);
+    }
+
+    #[test]
+    fn test_permissions_deny_all() {
+        assert_eq!(
+            serde_json::to_string(&Permissions::deny_all()).unwrap(),
+            r#"{"pub":{"deny":[">"]},"sub":{"deny":[">"]}}"#
+        );
+        assert_eq!(
+            serde_json::to_string(&Permissions::allow_all()).unwrap(),
+            r#"{"pub":{"allow":[">"]},"sub":{"allow":[">"]}}"#
+        );
+    }
+
+    #[test]
+    fn test_permission_normalize() {
+        let mut permission = Permission {
+            allow: vec!["b".to_string(), "a".to_string(), "a".to_string()],
+            deny: vec!["z".to_string(), "z".to_string()],
+        };
+        permission.normalize();
+        assert_eq!(permission.allow, ["a", "b"]);
+        assert_eq!(permission.deny, ["z"]);
+    }
+
+    #[test]
+    fn test_permission_allows() {
+        let permission = Permission {
+            allow: vec!["svc.>".to_string()],
+            deny: vec!["svc.admin.*".to_string()],
+        };
+        assert!(permission.allows("svc.orders"));
+        assert!(permission.allows("svc.orders.*"));
+        assert!(!permission.allows("svc.admin.users"));
+        assert!(!permission.allows("svc.>"));
+        assert!(!permission.allows("svc.*.users"));
+        assert!(!permission.allows("other"));
+        assert!(Permission::default().allows(">"));
+        assert!(!Permission::deny_all().allows("svc.>"));
+    }
+
+    #[test]
+    fn test_service_export_latency_results() {
+        for results in ["latency.*", "latency..orders", ""] {
+            let export = Export {
+                subject: "svc.orders".to_string(),
+                export_type: Some(ExportType::Service),
+                latency: Some(ServiceLatency {
+                    results: results.to_string(),
+                }),
+                ..Default::default()
+            };
+            let mut vr = ValidationResults::new();
+            export.validate(&mut vr);
+            assert!(vr.is_blocking(false), "{results}");
+        }
+    }
+
+    #[test]
+    fn test_service_export_is_valid() {
+        let export = Export {
+            subject: "svc.orders".to_string(),
+            export_type: Some(ExportType::Service),
+            response_type: Some(ResponseType::Stream),
+            response_threshold: Some(Duration::from_secs(1)),
+            latency: Some(ServiceLatency {
+                results: "latency.orders".to_string(),
+            }),
+            ..Default::default()
+        };
+        let mut vr = ValidationResults::new();
+        export.validate(&mut vr);
+        assert!(vr.is_empty());
+    }
+
+    #[test]
+    fn test_singleton_export_with_threshold() {
+        let mut export = Export {
+            subject: "svc.orders".to_string(),
+            export_type: Some(ExportType::Service),
+            response_type: Some(ResponseType::Singleton),
+            response_threshold: Some(Duration::from_secs(1)),
+            ..Default::default()
+        };
+        let mut vr = ValidationResults::new();
+        export.validate(&mut vr);
+        assert!(!vr.is_empty());
+        assert!(!vr.is_blocking(false));
+
+        export.response_type = Some(ResponseType::Chunked);
+        export.response_threshold = Some(Duration::ZERO);
+        let mut vr = ValidationResults::new();
+        export.validate(&mut vr);
+        assert!(!vr.is_empty());
+        assert!(!vr.is_blocking(false));
+    }
+
+    #[test]
+    fn test_account_token_position() {
+        let mut export = Export {
+            subject: "foo.*.bar".to_string(),
+            export_type: Some(ExportType::Service),
+            account_token_position: Some(2),
+            ..Default::default()
+        };
+        let mut vr = ValidationResults::new();
+        export.validate(&mut vr);
+        assert!(vr.is_empty());
+
+        for position in [0, 1, 4] {
+            export.account_token_position = Some(position);
+            let mut vr = ValidationResults::new();
+            export.validate(&mut vr);
+            assert!(vr.is_blocking(false), "position {position}");
+        }
+    }
+
+    #[test]
+    fn test_derive_token_position() {
+        let mut export = Export {
+            subject: "svc.*.req".to_string(),
+            export_type: Some(ExportType::Service),
+            ..Default::default()
+        };
+        export.derive_token_position().unwrap();
+        assert_eq!(export.account_token_position, Some(2));
+        let mut vr = ValidationResults::new();
+        export.validate(&mut vr);
+        assert!(vr.is_empty());
+
+        export.subject = "svc.orders.>".to_string();
+        assert!(export.derive_token_position().is_err());
+        assert_eq!(export.account_token_position, Some(2));
+    }
+
+    #[test]
+    fn test_add_tag() {
+        let mut fields = GenericFields::default();
+        fields.add_tag("Prod");
+        fields.add_tag("prod");
+        assert_eq!(fields.tags, Some(vec!["prod".to_string()]));
+    }
+
+    #[test]
+    fn test_remove_tag() {
+        let mut fields = GenericFields::default();
+        fields.add_tag("prod");
+        fields.add_tag("team-a");
+        assert!(fields.remove_tag("PROD"));
+        assert!(!fields.remove_tag("prod"));
+        assert_eq!(fields.tags, Some(vec!["team-a".to_string()]));
+    }
+
+    #[test]
+    fn test_normalize_tags() {
+        let mut fields = GenericFields {
+            tags: Some(vec!["Prod".into(), "team-a".into(), "PROD".into()]),
+            ..Default::default()
+        };
+        fields.normalize_tags();
+        assert_eq!(
+            fields.tags,
+            Some(vec!["prod".to_string(), "team-a".to_string()])
+        );
     }
 }
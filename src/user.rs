@@ -1,19 +1,92 @@
-use crate::types::{GenericFields, Limits, NatsLimits, Permissions};
-use crate::{Claim, ClaimType, Claims};
+use crate::authorization::AuthRequest;
+use crate::types::{GenericFields, Limits, NatsLimits, Permission, Permissions};
+use crate::validation::{is_valid_public_key, ValidationResults};
+use crate::{summary_line, Claim, ClaimType, Claims};
+use nkeys::{KeyPair, KeyPairType};
 use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display};
+use std::str::FromStr;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct User {
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub issuer_account: Option<String>,
     #[serde(flatten)]
     pub permissions: UserPermissionLimits,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub issuer_account: Option<String>,
     #[serde(flatten)]
     pub generic_fields: GenericFields,
 }
 
 impl Claim for User {
-    fn validate() {}
+    const CLAIM_TYPE: ClaimType = ClaimType::User;
+
+    fn validate(claims: &Claims<Self>, vr: &mut ValidationResults) {
+        if !claims.iss.is_empty() && claims.iss == claims.sub {
+            vr.add_error(format!(
+                "user {} is self-signed, but must be issued by an account",
+                claims.sub
+            ));
+        }
+        claims.nats.permissions.validate(vr);
+
+        if let Some(issuer_account) = &claims.nats.issuer_account {
+            if !is_valid_public_key(issuer_account, KeyPairType::Account) {
+                vr.add_error(format!(
+                    "issuer account {issuer_account} is not a valid account public key"
+                ));
+            } else if !claims.iss.is_empty()
+                && claims.iss != *issuer_account
+                && !is_valid_public_key(&claims.iss, KeyPairType::Account)
+            {
+                vr.add_error(format!(
+                    "user issued for account {issuer_account} must be signed by one of its signing keys, not {}",
+                    claims.iss
+                ));
+            }
+        }
+    }
+
+    fn generic_fields(&self) -> Option<&GenericFields> {
+        Some(&self.generic_fields)
+    }
+
+    fn normalize_permissions(&mut self) {
+        self.permissions.permissions.normalize();
+    }
+}
+
+impl Display for Claims<User> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_summary(f)?;
+        let user = &self.nats;
+        summary_line(
+            f,
+            "Issuer account",
+            user.issuer_account.as_deref().unwrap_or("-"),
+        )?;
+        summary_line(
+            f,
+            "Bearer token",
+            if user.is_bearer() { "yes" } else { "no" },
+        )?;
+        let permissions = &user.permissions.permissions;
+        summary_line(f, "Publish", permission_summary(&permissions.publish))?;
+        summary_line(f, "Subscribe", permission_summary(&permissions.subscribe))
+    }
+}
+
+fn permission_summary(permission: &Permission) -> String {
+    match (permission.allow.is_empty(), permission.deny.is_empty()) {
+        (true, true) => "all".to_string(),
+        (false, true) => format!("allow {}", permission.allow.join(", ")),
+        (true, false) => format!("deny {}", permission.deny.join(", ")),
+        (false, false) => format!(
+            "allow {}; deny {}",
+            permission.allow.join(", "),
+            permission.deny.join(", ")
+        ),
+    }
 }
 
 impl Default for User {
@@ -22,7 +95,7 @@ impl Default for User {
             permissions: UserPermissionLimits::default(),
             issuer_account: None,
             generic_fields: GenericFields {
-                claim_type: ClaimType::User,
+                claim_type: Self::CLAIM_TYPE,
                 ..Default::default()
             },
         }
@@ -37,9 +110,44 @@ impl User {
         claim.sub = nkey;
         claim
     }
+
+    /// Creates claims for the public key of `key`, which must be a user key.
+    pub fn new_claims_for(name: impl Into<String>, key: &KeyPair) -> anyhow::Result<Claims<User>> {
+        if key.key_pair_type() != KeyPairType::User {
+            return Err(anyhow::anyhow!("{} is not a user key", key.public_key()));
+        }
+        Ok(Self::new_claims(name.into(), key.public_key()))
+    }
+
+    /// Sets the account a user signed by an account signing key belongs to. This must be the
+    /// account's identity key, not the signing key.
+    pub fn set_issuer_account(&mut self, account_public: &str) -> anyhow::Result<()> {
+        if !is_valid_public_key(account_public, KeyPairType::Account) {
+            return Err(anyhow::anyhow!(
+                "issuer account {account_public} is not a valid account public key"
+            ));
+        }
+        self.issuer_account = Some(account_public.to_string());
+        Ok(())
+    }
+
+    /// Merges the client tags of an auth callout request into the user's tags, lowercasing and
+    /// de-duplicating them along with any tags already present.
+    pub fn apply_tags_from_request(&mut self, req: &AuthRequest) {
+        self.generic_fields.normalize_tags();
+        for tag in req.client_tags() {
+            self.generic_fields.add_tag(tag.as_str());
+        }
+    }
+
+    /// Returns `true` if the user JWT may be used without proving possession of the user nkey.
+    pub fn is_bearer(&self) -> bool {
+        self.permissions.bearer_token == Some(true)
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Hash, Eq, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct UserPermissionLimits {
     #[serde(flatten)]
     pub permissions: Permissions,
@@ -64,3 +172,203 @@ impl Default for UserPermissionLimits {
         }
     }
 }
+
+impl UserPermissionLimits {
+    /// Adds a connection type the user may connect with, ignoring duplicates.
+    pub fn allow_connection_type(&mut self, connection_type: ConnectionType) {
+        let connection_type = connection_type.to_string();
+        let types = self.allowed_connection_types.get_or_insert_with(Vec::new);
+        if !types.contains(&connection_type) {
+            types.push(connection_type);
+        }
+    }
+
+    pub fn validate(&self, vr: &mut ValidationResults) {
+        self.permissions.validate(vr);
+        if let Some(nats_limits) = self.limits.as_ref().and_then(|l| l.nats_limits.as_ref()) {
+            nats_limits.validate(vr);
+        }
+        for connection_type in self.allowed_connection_types.iter().flatten() {
+            if connection_type.parse::<ConnectionType>().is_err() {
+                vr.add_error(format!("unknown connection type {connection_type}"));
+            }
+        }
+    }
+}
+
+/// The connection types nats-server recognizes in `allowed_connection_types`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConnectionType {
+    Standard,
+    Websocket,
+    Leafnode,
+    LeafnodeWs,
+    Mqtt,
+    MqttWs,
+    InProcess,
+}
+
+impl Display for ConnectionType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConnectionType::Standard => write!(f, "STANDARD"),
+            ConnectionType::Websocket => write!(f, "WEBSOCKET"),
+            ConnectionType::Leafnode => write!(f, "LEAFNODE"),
+            ConnectionType::LeafnodeWs => write!(f, "LEAFNODE_WS"),
+            ConnectionType::Mqtt => write!(f, "MQTT"),
+            ConnectionType::MqttWs => write!(f, "MQTT_WS"),
+            ConnectionType::InProcess => write!(f, "IN_PROCESS"),
+        }
+    }
+}
+
+impl FromStr for ConnectionType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_uppercase().as_str() {
+            "STANDARD" => Ok(ConnectionType::Standard),
+            "WEBSOCKET" => Ok(ConnectionType::Websocket),
+            "LEAFNODE" => Ok(ConnectionType::Leafnode),
+            "LEAFNODE_WS" => Ok(ConnectionType::LeafnodeWs),
+            "MQTT" => Ok(ConnectionType::Mqtt),
+            "MQTT_WS" => Ok(ConnectionType::MqttWs),
+            "IN_PROCESS" => Ok(ConnectionType::InProcess),
+            _ => Err(anyhow::anyhow!("unknown connection type: {s}")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_display_summary() {
+        let key = KeyPair::new_user();
+        let mut claims = User::new_claims("alice".to_string(), key.public_key());
+        claims.nats.permissions.permissions.publish.allow = vec!["orders.>".to_string()];
+        let summary = claims.to_string();
+        assert!(summary.contains("alice"));
+        assert!(summary.contains(&key.public_key()));
+        assert!(summary.contains("allow orders.>"));
+    }
+
+    #[test]
+    fn test_set_issuer_account() {
+        let account_key = KeyPair::new_account();
+        let signing_key = KeyPair::new_account();
+        let mut claims = User::new_claims("test".to_string(), KeyPair::new_user().public_key());
+        claims.iss = signing_key.public_key();
+
+        assert!(claims
+            .nats
+            .set_issuer_account(&KeyPair::new_user().public_key())
+            .is_err());
+        claims.nats.issuer_account = Some(KeyPair::new_user().public_key());
+        let mut vr = ValidationResults::new();
+        claims.validate(&mut vr);
+        assert!(vr.is_blocking(false));
+
+        claims
+            .nats
+            .set_issuer_account(&account_key.public_key())
+            .unwrap();
+        let mut vr = ValidationResults::new();
+        claims.validate(&mut vr);
+        assert!(vr.is_empty());
+    }
+
+    #[test]
+    fn test_apply_tags_from_request() {
+        let mut req = AuthRequest::default();
+        req.client_info.tags = Some(vec!["team-a".to_string()]);
+        assert_eq!(req.client_tags(), ["team-a"]);
+
+        let mut user = User::default();
+        user.apply_tags_from_request(&req);
+        assert_eq!(user.generic_fields.tags, Some(vec!["team-a".to_string()]));
+
+        user.apply_tags_from_request(&req);
+        assert_eq!(user.generic_fields.tags, Some(vec!["team-a".to_string()]));
+        assert!(AuthRequest::default().client_tags().is_empty());
+    }
+
+    #[test]
+    fn test_new_claims_for() {
+        let user = KeyPair::new_user();
+        let claims = User::new_claims_for("alice", &user).unwrap();
+        assert_eq!(claims.sub, user.public_key());
+        assert_eq!(claims.name.as_deref(), Some("alice"));
+
+        assert!(User::new_claims_for("alice", &KeyPair::new_account()).is_err());
+    }
+
+    #[test]
+    fn test_connection_type_parse() {
+        assert_eq!(
+            "leafnode_ws".parse::<ConnectionType>().unwrap(),
+            ConnectionType::LeafnodeWs
+        );
+        assert_eq!(
+            "Standard".parse::<ConnectionType>().unwrap(),
+            ConnectionType::Standard
+        );
+        assert_eq!(ConnectionType::InProcess.to_string(), "IN_PROCESS");
+        assert!("carrier_pigeon".parse::<ConnectionType>().is_err());
+    }
+
+    #[test]
+    fn test_claim_type() {
+        assert_eq!(User::CLAIM_TYPE, ClaimType::User);
+        let user = User::new_claims("test".to_string(), "UABC".to_string());
+        assert_eq!(user.nats.generic_fields.claim_type, User::CLAIM_TYPE);
+    }
+
+    #[test]
+    fn test_self_signed_user() {
+        let key = nkeys::KeyPair::new_user();
+        let user = User::new_claims("test".to_string(), key.public_key());
+        let user = Claims::<User>::decode(&user.encode(&key).unwrap()).unwrap();
+
+        let mut vr = ValidationResults::new();
+        user.validate(&mut vr);
+        assert!(vr.is_blocking(false));
+
+        let user = User::new_claims("test".to_string(), key.public_key());
+        let user =
+            Claims::<User>::decode(&user.encode(&nkeys::KeyPair::new_account()).unwrap()).unwrap();
+        let mut vr = ValidationResults::new();
+        user.validate(&mut vr);
+        assert!(vr.is_empty());
+    }
+
+    #[test]
+    fn test_allowed_connection_types() {
+        let mut user = User::new_claims("test".to_string(), "UABC".to_string());
+        user.nats
+            .permissions
+            .allow_connection_type(ConnectionType::Websocket);
+        user.nats
+            .permissions
+            .allow_connection_type(ConnectionType::Websocket);
+        assert_eq!(
+            user.nats.permissions.allowed_connection_types,
+            Some(vec!["WEBSOCKET".to_string()])
+        );
+
+        let mut vr = ValidationResults::new();
+        user.validate(&mut vr);
+        assert!(!vr.is_blocking(false));
+
+        user.nats
+            .permissions
+            .allowed_connection_types
+            .as_mut()
+            .unwrap()
+            .push("carrier_pigeon".to_string());
+        let mut vr = ValidationResults::new();
+        user.validate(&mut vr);
+        assert!(vr.is_blocking(false));
+    }
+}
@@ -1,6 +1,12 @@
+use nkeys::KeyPairType;
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 
+/// Returns `true` if `key` is a well-formed public nkey of the given type.
+pub fn is_valid_public_key(key: &str, kind: KeyPairType) -> bool {
+    nkeys::from_public_key(key).is_ok_and(|(prefix, _)| KeyPairType::from(prefix) == kind)
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, Hash, Eq, PartialEq)]
 pub struct ValidationIssue {
     pub description: String,
@@ -31,6 +37,14 @@ impl ValidationResults {
         });
     }
 
+    pub fn add_warning(&mut self, description: String) {
+        self.issues.insert(ValidationIssue {
+            description,
+            blocking: false,
+            time_check: false,
+        });
+    }
+
     pub fn add_time_check(&mut self, description: String) {
         self.issues.insert(ValidationIssue {
             description,
@@ -39,10 +53,20 @@ impl ValidationResults {
         });
     }
 
+    pub fn issues(&self) -> impl Iterator<Item = &ValidationIssue> {
+        self.issues.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Returns `true` if any issue is blocking, counting time checks as blocking only when
+    /// `time_checks` is set.
     pub fn is_blocking(&self, time_checks: bool) -> bool {
         self.issues
             .iter()
-            .any(|i| i.blocking && (!i.time_check || time_checks))
+            .any(|i| i.blocking || (time_checks && i.time_check))
     }
 }
 
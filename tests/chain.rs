@@ -0,0 +1,197 @@
+use indexmap::IndexSet;
+use nats_jwt_rs::{
+    account::Account,
+    chain::{verify_chain, verify_many},
+    operator::Operator,
+    types::SigningKey,
+    user::User,
+    ClaimType, JwtError,
+};
+use nkeys::KeyPair;
+
+struct Chain {
+    operator_key: KeyPair,
+    account_key: KeyPair,
+    account_signing_key: KeyPair,
+    operator_jwt: String,
+    account_jwt: String,
+}
+
+fn chain() -> Chain {
+    let operator_key = KeyPair::new_operator();
+    let account_key = KeyPair::new_account();
+    let account_signing_key = KeyPair::new_account();
+
+    let operator = Operator::new_claims("operator".to_string(), operator_key.public_key());
+    let operator_jwt = operator.encode(&operator_key).unwrap();
+
+    let mut account = Account::new_claims("account".to_string(), account_key.public_key());
+    account.nats.signing_keys = Some(IndexSet::from([SigningKey {
+        key: account_signing_key.public_key(),
+        scope: None,
+    }]));
+    let account_jwt = account.encode(&operator_key).unwrap();
+
+    Chain {
+        operator_key,
+        account_key,
+        account_signing_key,
+        operator_jwt,
+        account_jwt,
+    }
+}
+
+#[test]
+fn test_valid_chain() {
+    let chain = chain();
+    let user_key = KeyPair::new_user();
+    let user = User::new_claims("user".to_string(), user_key.public_key());
+    let user_jwt = user.encode(&chain.account_key).unwrap();
+
+    let verified = verify_chain(&chain.operator_jwt, &chain.account_jwt, &user_jwt).unwrap();
+    assert_eq!(verified.operator.sub, chain.operator_key.public_key());
+    assert_eq!(verified.account.sub, chain.account_key.public_key());
+    assert_eq!(verified.user.sub, user_key.public_key());
+}
+
+#[test]
+fn test_valid_chain_with_signing_key() {
+    let chain = chain();
+    let mut user = User::new_claims("user".to_string(), KeyPair::new_user().public_key());
+    user.nats.issuer_account = Some(chain.account_key.public_key());
+    let user_jwt = user.encode(&chain.account_signing_key).unwrap();
+
+    assert!(verify_chain(&chain.operator_jwt, &chain.account_jwt, &user_jwt).is_ok());
+}
+
+#[test]
+fn test_user_signed_by_wrong_account() {
+    let chain = chain();
+    let user = User::new_claims("user".to_string(), KeyPair::new_user().public_key());
+    let user_jwt = user.encode(&KeyPair::new_account()).unwrap();
+
+    assert!(verify_chain(&chain.operator_jwt, &chain.account_jwt, &user_jwt).is_err());
+}
+
+#[test]
+fn test_account_signed_by_wrong_operator() {
+    let chain = chain();
+    let account = Account::new_claims("account".to_string(), chain.account_key.public_key());
+    let account_jwt = account.encode(&KeyPair::new_operator()).unwrap();
+    let user = User::new_claims("user".to_string(), KeyPair::new_user().public_key());
+    let user_jwt = user.encode(&chain.account_key).unwrap();
+
+    assert!(verify_chain(&chain.operator_jwt, &account_jwt, &user_jwt).is_err());
+}
+
+#[test]
+fn test_swapped_token_kinds() {
+    let chain = chain();
+    let user = User::new_claims("user".to_string(), KeyPair::new_user().public_key());
+    let user_jwt = user.encode(&chain.account_key).unwrap();
+
+    // an operator token in the account slot passes the issuer check, as it is self-signed
+    assert!(verify_chain(&chain.operator_jwt, &chain.operator_jwt, &user_jwt).is_err());
+    assert!(verify_chain(&chain.account_jwt, &chain.account_jwt, &user_jwt).is_err());
+    assert!(verify_chain(&chain.operator_jwt, &chain.account_jwt, &chain.account_jwt).is_err());
+    assert!(verify_chain(&user_jwt, &chain.account_jwt, &user_jwt).is_err());
+
+    // a user claim for an account key, issued by the account
+    let user = User::new_claims("user".to_string(), KeyPair::new_account().public_key());
+    let user_jwt = user.encode(&chain.account_key).unwrap();
+    assert!(verify_chain(&chain.operator_jwt, &chain.account_jwt, &user_jwt).is_err());
+
+    // an account claim for a user key, issued by the operator, with a user issued by that key
+    let account_key = KeyPair::new_user();
+    let account = Account::new_claims("account".to_string(), account_key.public_key());
+    let account_jwt = account.encode(&chain.operator_key).unwrap();
+    let user = User::new_claims("user".to_string(), KeyPair::new_user().public_key());
+    let user_jwt = user.encode(&account_key).unwrap();
+    assert!(verify_chain(&chain.operator_jwt, &account_jwt, &user_jwt).is_err());
+}
+
+#[test]
+fn test_expired_user() {
+    let chain = chain();
+    let mut user = User::new_claims("user".to_string(), KeyPair::new_user().public_key());
+    user.exp = Some(1);
+    let user_jwt = user.encode(&chain.account_key).unwrap();
+
+    assert!(verify_chain(&chain.operator_jwt, &chain.account_jwt, &user_jwt).is_err());
+}
+
+#[test]
+fn test_verify_many() {
+    let chain = chain();
+    let accounts: Vec<String> = (0..4)
+        .map(|i| {
+            Account::new_claims(format!("account-{i}"), KeyPair::new_account().public_key())
+                .encode(&chain.operator_key)
+                .unwrap()
+        })
+        .collect();
+    let (corrupt, _) = accounts[2].rsplit_once('.').unwrap();
+    let corrupt = format!("{corrupt}.{}", accounts[1].rsplit_once('.').unwrap().1);
+    let foreign = Account::new_claims("foreign".to_string(), KeyPair::new_account().public_key())
+        .encode(&KeyPair::new_operator())
+        .unwrap();
+
+    let tokens = [
+        accounts[0].as_str(),
+        accounts[1].as_str(),
+        corrupt.as_str(),
+        accounts[3].as_str(),
+        foreign.as_str(),
+        chain.operator_jwt.as_str(),
+    ];
+    let trusted = KeyPair::from_public_key(&chain.operator_key.public_key()).unwrap();
+    let results = verify_many(&tokens, &trusted);
+
+    assert_eq!(results.len(), tokens.len());
+    assert_eq!(
+        results[0].as_ref().unwrap().name.as_deref(),
+        Some("account-0")
+    );
+    assert_eq!(
+        results[1].as_ref().unwrap().name.as_deref(),
+        Some("account-1")
+    );
+    assert!(matches!(results[2], Err(JwtError::SignatureVerification)));
+    assert_eq!(
+        results[3].as_ref().unwrap().name.as_deref(),
+        Some("account-3")
+    );
+    assert!(matches!(results[4], Err(JwtError::InvalidIssuer(_))));
+    assert!(matches!(
+        results[5],
+        Err(JwtError::ClaimTypeMismatch {
+            expected: ClaimType::Account,
+            found: ClaimType::Operator,
+        })
+    ));
+}
+
+#[test]
+fn test_strict_signing_key_usage() {
+    let operator_key = KeyPair::new_operator();
+    let signing_key = KeyPair::new_operator();
+    let account_key = KeyPair::new_account();
+
+    let mut operator = Operator::new_claims("operator".to_string(), operator_key.public_key());
+    operator.nats.strict_signing_key_usage = Some(true);
+    operator
+        .nats
+        .add_signing_key(signing_key.public_key())
+        .unwrap();
+    let operator_jwt = operator.encode(&operator_key).unwrap();
+
+    let account = Account::new_claims("account".to_string(), account_key.public_key());
+    let user = User::new_claims("user".to_string(), KeyPair::new_user().public_key());
+    let user_jwt = user.encode(&account_key).unwrap();
+
+    let identity_signed = account.encode(&operator_key).unwrap();
+    assert!(verify_chain(&operator_jwt, &identity_signed, &user_jwt).is_err());
+
+    let signing_key_signed = account.encode(&signing_key).unwrap();
+    assert!(verify_chain(&operator_jwt, &signing_key_signed, &user_jwt).is_ok());
+}
@@ -0,0 +1,45 @@
+#![cfg(feature = "proptest")]
+
+use nats_jwt_rs::{account::Account, operator::Operator, user::User, Claim, Claims};
+use nkeys::KeyPair;
+use proptest::prelude::*;
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Encodes `claims`, decodes the token and checks that everything but the issue-time fields
+/// survived, comparing payloads by their serialized form.
+fn round_trip<T>(claims: &Claims<T>, key: &KeyPair) -> Result<(), TestCaseError>
+where
+    T: Claim + DeserializeOwned + Serialize + Clone,
+{
+    let token = claims.encode(key).unwrap();
+    let decoded = Claims::<T>::decode(&token).unwrap();
+
+    prop_assert_eq!(&decoded.sub, &claims.sub);
+    prop_assert_eq!(&decoded.name, &claims.name);
+    prop_assert_eq!(&decoded.aud, &claims.aud);
+    prop_assert_eq!(decoded.exp, claims.exp);
+    prop_assert_eq!(
+        serde_json::to_value(&decoded.nats).unwrap(),
+        serde_json::to_value(&claims.nats).unwrap()
+    );
+    Ok(())
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn user_round_trip(claims in any::<Claims<User>>()) {
+        round_trip(&claims, &KeyPair::new_account())?;
+    }
+
+    #[test]
+    fn account_round_trip(claims in any::<Claims<Account>>()) {
+        round_trip(&claims, &KeyPair::new_operator())?;
+    }
+
+    #[test]
+    fn operator_round_trip(claims in any::<Claims<Operator>>()) {
+        round_trip(&claims, &KeyPair::new_operator())?;
+    }
+}